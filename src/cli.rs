@@ -1,6 +1,7 @@
 // FILE: src/cli.rs
 
 use clap::{Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -23,14 +24,41 @@ pub enum Commands {
     /// Removes the path header from files
     Remove(Args),
     /// Removes all comments from files, except for the path header
-    Clean(Args),
+    Clean(CleanArgs),
     /// Copies the content of multiple files to the clipboard
     Copy(Args),
+    /// Reports missing, stale or malformed path headers without changing anything
+    Check(CheckArgs),
+    /// Exports every comment with its file, lines, kind and text, as JSON or CSV
+    Comments(CommentsArgs),
+    /// Adds or updates an SPDX license block below the path header
+    License(LicenseArgs),
+    /// Rewrites only the path headers that point at an old location
+    Sync(SyncArgs),
+    /// Keeps headers (and optionally a `--output` bundle) up to date as files change
+    Watch(WatchArgs),
     /// Creates a file/folder structure from a text file
     Structure(StructureArgs),
+    /// Reverts a run of add, remove, clean, sync, license, watch or structure
+    Undo(UndoArgs),
+    /// Lists past runs that can be undone
+    History(HistoryArgs),
+    /// Inspects the layered configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Prints the merged configuration and where each value came from
+    Show {
+        /// The directory whose `.filedress.toml` should be used. Defaults to the current directory.
+        directory: Option<PathBuf>,
+    },
 }
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ProjectType {
     Rust,
     Python,
@@ -45,7 +73,7 @@ pub struct Args {
     #[arg(required = true)]
     pub directory: PathBuf,
     /// A preset for common project types (e.g., rust, python, web)
-    #[arg(long, exclusive = true)]
+    #[arg(long)]
     pub project: Option<ProjectType>,
     /// A custom list of file extensions to process (e.g., "ts,js,css")
     #[arg(long, value_delimiter = ',', conflicts_with = "project")]
@@ -59,9 +87,141 @@ pub struct Args {
     /// Overwrites an existing path header if one is found
     #[arg(short, long, default_value_t = false)]
     pub force: bool,
+    /// Rewrites headers written with a legacy template to the current one
+    #[arg(long, default_value_t = false)]
+    pub migrate: bool,
     /// Write the output to a file instead of the clipboard
     #[arg(long, short)]
     pub output: Option<PathBuf>,
+    /// Don't respect .gitignore, .ignore or .filedressignore files
+    #[arg(long, default_value_t = false)]
+    pub no_ignore: bool,
+    /// Only process files whose path relative to the directory matches this glob (repeatable)
+    #[arg(long)]
+    pub include: Vec<String>,
+    /// Skip files whose path relative to the directory matches this glob (repeatable)
+    #[arg(long)]
+    pub exclude: Vec<String>,
+    /// Show what would change as unified diffs, without writing anything
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+    /// How many files to process in parallel. Defaults to the number of CPUs.
+    #[arg(short, long)]
+    pub jobs: Option<usize>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+pub struct CheckArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// How to print the report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Parser, Debug, Default)]
+pub struct CommentsArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// How to write the comments
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+    pub format: ExportFormat,
+    /// Only export comments whose text matches this regex, e.g. "TODO|FIXME|HACK" (repeatable)
+    #[arg(long, value_name = "REGEX")]
+    pub filter: Vec<String>,
+    /// Also label comments matching this regex as directives, like `clean --keep-pattern` (repeatable)
+    #[arg(long = "keep-pattern", value_name = "REGEX")]
+    pub keep_patterns: Vec<String>,
+}
+
+#[derive(Parser, Debug, Default)]
+pub struct CleanArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// Keep documentation comments (e.g. `///`, `/** */`) and remove only ordinary ones
+    #[arg(long, default_value_t = false)]
+    pub keep_docs: bool,
+    /// Also keep comments whose text matches this regex, besides the built-in directives (repeatable)
+    #[arg(long = "keep-pattern", value_name = "REGEX")]
+    pub keep_patterns: Vec<String>,
+    /// Also remove Python docstrings; one that is the only statement of its body becomes `pass`
+    #[arg(long, default_value_t = false, overrides_with = "keep_docstrings")]
+    pub strip_docstrings: bool,
+    /// Keep Python docstrings (the default), overriding `strip_docstrings` from config
+    #[arg(long, default_value_t = false, overrides_with = "strip_docstrings")]
+    pub keep_docstrings: bool,
+    /// Allow at most N blank lines in a row; 0 removes every blank line (default: leave them alone)
+    #[arg(long, value_name = "N")]
+    pub collapse_blank_lines: Option<usize>,
+}
+
+#[derive(Parser, Debug)]
+pub struct LicenseArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// The SPDX license identifier (e.g. "MIT"). Overrides `spdx` in the [license] config section.
+    #[arg(long)]
+    pub spdx: Option<String>,
+    /// The copyright holder. Overrides `holder` in the [license] config section.
+    #[arg(long)]
+    pub holder: Option<String>,
+    /// Only report files whose license block is missing or out of date, and fail if there are any
+    #[arg(long, default_value_t = false)]
+    pub check: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct SyncArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// Only look at files git reports as renamed since REV (default: HEAD), e.g. `--git-renames=main`
+    #[arg(long, value_name = "REV", num_args = 0..=1, require_equals = true, default_missing_value = "HEAD")]
+    pub git_renames: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub args: Args,
+    /// How long to wait for a burst of file events to settle before acting, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub debounce: u64,
+}
+
+#[derive(Parser, Debug)]
+pub struct UndoArgs {
+    /// The run to revert, as shown by `filedress history`. Defaults to the latest run in the current directory not yet undone.
+    pub run_id: Option<String>,
+    /// Also restore files that were edited after the run, discarding those edits
+    #[arg(short, long, default_value_t = false)]
+    pub force: bool,
+    /// Show what would be restored without changing anything
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    /// How many runs to show, newest first
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+    /// List runs in every directory, not just the current one
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
 }
 
 #[derive(Parser, Debug)]
@@ -75,6 +235,9 @@ pub struct StructureArgs {
     /// The number of spaces that represent one level of indentation.
     #[arg(short, long, default_value_t = 4)]
     pub indent: u32,
+    /// List the paths that would be created, without creating anything
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
 }
 
 impl Default for Args {
@@ -86,7 +249,13 @@ impl Default for Args {
             up: 0,
             depth: None,
             force: false,
+            migrate: false,
             output: None, // Add the default for our new field
+            no_ignore: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            dry_run: false,
+            jobs: None,
         }
     }
 }
@@ -1,55 +1,75 @@
 // src/commands/add.rs
 
 use anyhow::Result;
-use std::fs;
-use std::io::{BufRead, BufReader};
+use std::path::Path;
 
 use crate::cli::Args;
-use crate::file_utils::get_comment_style;
-use super::utils::{create_file_walker, generate_display_path, resolve_extensions};
+use crate::header::{header_line_index, line_offset, HeaderFormat};
+use crate::languages::LanguageRegistry;
+use crate::text::TextFile;
+use super::changes::{ChangeWriter, Outcome};
+use super::utils::{collect_files, load_header_format, load_registry, process_in_order, DisplayPaths, FileSelector};
 
 pub fn add(args: &Args) -> Result<()> {
     println!("Searching in: {:?}", &args.directory);
-    let extensions = resolve_extensions(args);
-    let walker = create_file_walker(&args.directory, &extensions, args.depth);
+    let registry = load_registry(&args.directory)?;
+    let format = load_header_format(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let files = collect_files(args, &selector)?;
+    let display_paths = DisplayPaths::new(&args.directory, args.up)?;
+    let mut writer = ChangeWriter::new(&args.directory, args.dry_run).journaled("add");
 
-    for entry in walker {
-        let file_path = entry.path();
-        let display_path = generate_display_path(file_path, &args.directory, args.up)?;
-        let (prefix, suffix) = get_comment_style(file_path);
-        
-        // FIX: Only add a space before the suffix if the suffix is not empty.
-        let header = if suffix.is_empty() {
-            format!("{} Path:{}", prefix, display_path.display()).trim().to_string()
-        } else {
-            format!("{} Path: {} {}", prefix, display_path.display(), suffix).trim().to_string()
-        };
-
-        let mut first_line = String::new();
-        if fs::File::open(file_path).and_then(|f| BufReader::new(f).read_line(&mut first_line)).is_err() {
-            continue;
-        }
+    process_in_order(
+        &files,
+        args.jobs,
+        |path| add_header(path, args, &registry, &format, &display_paths),
+        |path, outcome| match outcome {
+            Some(outcome) => writer.apply(path, outcome),
+            None => Ok(()),
+        },
+    )?;
+    writer.finish()?;
+    println!("\n'add' command finished.");
+    Ok(())
+}
 
-        let is_path_header = first_line.trim().starts_with(&format!("{} Path:", prefix));
+/// Works out the new content of one file. Returns `None` for files that can't be read as text.
+pub(super) fn add_header(
+    file_path: &Path,
+    args: &Args,
+    registry: &LanguageRegistry,
+    format: &HeaderFormat,
+    display_paths: &DisplayPaths,
+) -> Result<Option<Outcome>> {
+    let Ok(file) = TextFile::read(file_path) else {
+        return Ok(None);
+    };
+    let content = &file.content;
+    let language = registry.language_for(file_path);
+    let display_path = display_paths.display_path(file_path)?;
+    let header = format.render_block(language, file_path, &display_path);
 
-        if is_path_header && !args.force {
-            println!("[SKIP] Header exists (use --force to overwrite): {}", file_path.display());
-            continue;
+    let (new_content, action) = match format.find(content, language) {
+        // An existing header is replaced in place, keeping whatever surrounds it.
+        Some(found) if args.force || (found.outdated && args.migrate) => {
+            let action = if found.outdated && !args.force { "[MIGRATED]" } else { "[REPLACED]" };
+            let (before, after) = (&content[..found.range.start], &content[found.range.end..]);
+            (format!("{}{}{}", before, header, after), action)
         }
+        Some(found) => {
+            let hint = if found.outdated { "use --migrate to update it" } else { "use --force to overwrite" };
+            return Ok(Some(Outcome::unchanged(format!("[SKIP] Header exists ({}): {}", hint, file_path.display()))));
+        }
+        None => {
+            // The header goes after a shebang and anything else that has to come first.
+            let (before, after) = content.split_at(line_offset(content, header_line_index(content, language)));
+            let separator = if before.is_empty() || before.ends_with('\n') { "" } else { "\n" };
+            // A blank line after the header only separates it from code that follows.
+            let header = if after.is_empty() { format!("{}\n", header.trim_end()) } else { header };
+            (format!("{}{}{}{}", before, separator, header, after), "[ADDED]")
+        }
+    };
 
-        let original_content = if is_path_header && args.force {
-            let full_content = fs::read_to_string(file_path)?;
-            full_content.lines().skip(1).collect::<Vec<&str>>().join("\n")
-        } else {
-            fs::read_to_string(file_path)?
-        };
-
-        let new_content = format!("{}\n{}", header, original_content);
-        fs::write(file_path, new_content)?;
-
-        let action = if is_path_header && args.force { "[REPLACED]" } else { "[ADDED]" };
-        println!("{} Header in: {}", action, file_path.display());
-    }
-    println!("\n'add' command finished.");
-    Ok(())
-}
\ No newline at end of file
+    let message = format!("{} Header in: {}", action, file_path.display());
+    Ok(Some(Outcome::edited(file, &new_content, message)))
+}
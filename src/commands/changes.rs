@@ -0,0 +1,288 @@
+// src/commands/changes.rs
+
+use anyhow::{Context, Result};
+use similar::{ChangeTag, TextDiff};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::journal::Journal;
+use crate::text::TextFile;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders a unified diff with `a/` and `b/` headers and three lines of context.
+pub fn unified_diff(shown_path: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", shown_path), &format!("b/{}", shown_path))
+        .to_string()
+}
+
+/// What a command decided to do with one file. Outcomes are computed in parallel and
+/// applied in walk order by `ChangeWriter::apply`.
+pub struct Outcome {
+    /// The file's current and new content, if it changes.
+    change: Option<(String, String)>,
+    /// The line reported for the file, e.g. `[ADDED] Header in: ...`.
+    message: String,
+}
+
+impl Outcome {
+    pub fn changed(old: String, new: String, message: String) -> Self {
+        Outcome { change: Some((old, new)), message }
+    }
+
+    /// A change to `file`, whose edited content is re-encoded in the file's own style.
+    pub fn edited(file: TextFile, new_content: &str, message: String) -> Self {
+        let new = file.encode(new_content);
+        Outcome::changed(file.raw, new, message)
+    }
+
+    pub fn unchanged(message: String) -> Self {
+        Outcome { change: None, message }
+    }
+
+    /// The content the file will have after this outcome is applied, if it changes.
+    pub fn new_content(&self) -> Option<&str> {
+        self.change.as_ref().map(|(_, new)| new.as_str())
+    }
+}
+
+/// The single place where mutating commands touch the disk.
+///
+/// In dry-run mode nothing is written; each change is printed as a unified diff
+/// instead, and `finish` prints a summary of what would have happened.
+/// Otherwise, if a journal was started with `journaled`, every change is appended to
+/// it before it is made, so `undo` can revert the run even if it never finishes.
+pub struct ChangeWriter {
+    root: PathBuf,
+    dry_run: bool,
+    journal: Option<Journal>,
+    color: bool,
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+    paths_created: usize,
+}
+
+impl ChangeWriter {
+    /// `root` is only used to shorten the paths shown in diffs.
+    pub fn new(root: &Path, dry_run: bool) -> Self {
+        ChangeWriter {
+            root: root.to_path_buf(),
+            dry_run,
+            journal: None,
+            color: atty::is(atty::Stream::Stdout) && std::env::var_os("NO_COLOR").is_none(),
+            files_changed: 0,
+            insertions: 0,
+            deletions: 0,
+            paths_created: 0,
+        }
+    }
+
+    /// Records every change in a journal for `command`, so `filedress undo` can revert the run.
+    pub fn journaled(mut self, command: &str) -> Self {
+        if !self.dry_run {
+            self.journal = Some(Journal::new(command, &self.root));
+        }
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Replaces the content of `path` (currently `old`) with `new`.
+    pub fn write(&mut self, path: &Path, old: &str, new: &str) -> Result<()> {
+        self.files_changed += 1;
+        let diff = TextDiff::from_lines(old, new);
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => self.insertions += 1,
+                ChangeTag::Delete => self.deletions += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+
+        if self.dry_run {
+            self.print_diff(&unified_diff(&self.display_path(path), old, new));
+            return Ok(());
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.record_write(path, old, new)?;
+        }
+        fs::write(path, new).with_context(|| format!("Failed to write file: {}", path.display()))
+    }
+
+    /// Deletes a file. Used by `undo`, which is not journaled itself.
+    pub fn remove_file(&mut self, path: &Path) -> Result<()> {
+        self.files_changed += 1;
+        if self.dry_run {
+            println!("[WOULD DELETE FILE] {}", path.display());
+            return Ok(());
+        }
+        fs::remove_file(path).with_context(|| format!("Failed to delete file: {}", path.display()))
+    }
+
+    /// Deletes a directory if it is empty, returning whether it did.
+    pub fn remove_empty_dir(&mut self, path: &Path) -> Result<bool> {
+        if fs::read_dir(path).map_or(true, |mut entries| entries.next().is_some()) {
+            return Ok(false);
+        }
+        if self.dry_run {
+            println!("[WOULD DELETE DIR]  {}", path.display());
+            return Ok(true);
+        }
+        fs::remove_dir(path).with_context(|| format!("Failed to delete directory: {}", path.display()))?;
+        Ok(true)
+    }
+
+    /// Writes the outcome's change, if any, then prints its message.
+    pub fn apply(&mut self, path: &Path, outcome: Outcome) -> Result<()> {
+        if let Some((old, new)) = outcome.change {
+            self.write(path, &old, &new)?;
+        }
+        println!("{}", outcome.message);
+        Ok(())
+    }
+
+    /// Creates a directory (and its parents).
+    pub fn create_dir(&mut self, path: &Path) -> Result<()> {
+        self.paths_created += 1;
+        if self.dry_run {
+            println!("[WOULD CREATE DIR]  {}", path.display());
+            return Ok(());
+        }
+        self.record_missing_dirs(path)?;
+        fs::create_dir_all(path).with_context(|| format!("Failed to create directory: {:?}", path))
+    }
+
+    /// Journals the directories `create_dir_all(path)` is about to create, outermost first.
+    fn record_missing_dirs(&mut self, path: &Path) -> Result<()> {
+        if let Some(journal) = &mut self.journal {
+            let missing: Vec<&Path> = path.ancestors().take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists()).collect();
+            for dir in missing.iter().rev() {
+                journal.record_created_dir(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates an empty file, creating its parent directories as needed.
+    pub fn create_file(&mut self, path: &Path) -> Result<()> {
+        self.paths_created += 1;
+        if self.dry_run {
+            println!("[WOULD CREATE FILE] {}", path.display());
+            return Ok(());
+        }
+        if let Some(parent_dir) = path.parent() {
+            self.record_missing_dirs(parent_dir)?;
+            fs::create_dir_all(parent_dir)
+                .with_context(|| format!("Failed to create parent directory for file: {:?}", path))?;
+        }
+        if let Some(journal) = &mut self.journal {
+            if path.exists() {
+                // The file is truncated below, so its content has to be kept to undo that.
+                let original = fs::read_to_string(path)
+                    .with_context(|| format!("Refusing to truncate a file the journal can't store: {}", path.display()))?;
+                journal.record_write(path, &original, "")?;
+            } else {
+                journal.record_created_file(path, "")?;
+            }
+        }
+        fs::File::create(path).with_context(|| format!("Failed to create file: {:?}", path))?;
+        Ok(())
+    }
+
+    /// Prints the dry-run summary, or closes the journal of a real run.
+    /// The writer can be used again afterwards; later changes go into a new journal.
+    pub fn finish(&mut self) -> Result<()> {
+        if self.dry_run {
+            println!(
+                "\nDry run: {} file(s) would change ({} insertion(s), {} deletion(s)), {} path(s) would be created. Nothing was written.",
+                self.files_changed, self.insertions, self.deletions, self.paths_created
+            );
+            return Ok(());
+        }
+        if let Some(journal) = &mut self.journal
+            && let Some(id) = journal.close()
+        {
+            println!("Recorded as run {} (revert it with `filedress undo {}`).", id, id);
+            *journal = Journal::new(&journal.command, &journal.directory);
+        }
+        Ok(())
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    fn print_diff(&self, unified: &str) {
+        for line in unified.lines() {
+            let color = if !self.color {
+                ""
+            } else if line.starts_with("---") || line.starts_with("+++") {
+                BOLD
+            } else if line.starts_with("@@") {
+                CYAN
+            } else if line.starts_with('+') {
+                GREEN
+            } else if line.starts_with('-') {
+                RED
+            } else {
+                ""
+            };
+            if color.is_empty() {
+                println!("{}", line);
+            } else {
+                println!("{}{}{}", color, line, RESET);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_snapshot() {
+        let old = "fn main() {\n    run(); // go\n}\n";
+        let new = "// Path:src/main.rs\nfn main() {\n    run();\n}\n";
+        let expected = "\
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
++// Path:src/main.rs
+ fn main() {
+-    run(); // go
++    run();
+ }
+";
+        assert_eq!(unified_diff("src/main.rs", old, new), expected);
+    }
+
+    #[test]
+    fn test_dry_run_does_not_write() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let file = temp_dir.path().join("a.py");
+        fs::write(&file, "pass\n")?;
+
+        let mut writer = ChangeWriter::new(temp_dir.path(), true);
+        writer.write(&file, "pass\n", "# Path:a.py\npass\n")?;
+        writer.create_file(&temp_dir.path().join("new.py"))?;
+
+        assert_eq!(fs::read_to_string(&file)?, "pass\n");
+        assert!(!temp_dir.path().join("new.py").exists());
+        assert_eq!((writer.files_changed, writer.insertions, writer.paths_created), (1, 1, 1));
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+// src/commands/check.rs
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::cli::{CheckArgs, OutputFormat};
+use crate::header::HeaderFormat;
+use crate::languages::LanguageRegistry;
+use crate::text::TextFile;
+use super::utils::{collect_files, load_header_format, load_registry, process_in_order, DisplayPaths, FileSelector};
+
+/// What is wrong with a file's path header.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Problem {
+    /// No `Path:` header where `add` would put one.
+    Missing,
+    /// The header names a path other than the file's current one.
+    Stale,
+    /// The header uses a different comment style than the file's language.
+    Malformed,
+    /// The header was written with a legacy template.
+    Outdated,
+}
+
+impl Problem {
+    fn label(self) -> &'static str {
+        match self {
+            Problem::Missing => "[MISSING]",
+            Problem::Stale => "[STALE]",
+            Problem::Malformed => "[MALFORMED]",
+            Problem::Outdated => "[OUTDATED]",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Violation {
+    file: String,
+    problem: Problem,
+    /// The header `add` would write.
+    expected: String,
+    /// The header line currently in the file, if any.
+    found: Option<String>,
+}
+
+/// The result of checking one file.
+enum Checked {
+    /// The file can't be read as text.
+    Skipped,
+    Ok,
+    Violation(Violation),
+}
+
+#[derive(Serialize, Debug)]
+struct Report {
+    files_checked: usize,
+    violations: Vec<Violation>,
+}
+
+/// Handles the 'check' subcommand logic. Never modifies files; fails if any header needs fixing.
+pub fn check(check_args: &CheckArgs) -> Result<()> {
+    let args = &check_args.args;
+    if check_args.format == OutputFormat::Text {
+        println!("Checking headers in: {:?}", &args.directory);
+    }
+    let registry = load_registry(&args.directory)?;
+    let format = load_header_format(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let files = collect_files(args, &selector)?;
+    let display_paths = DisplayPaths::new(&args.directory, args.up)?;
+
+    let mut report = Report { files_checked: 0, violations: Vec::new() };
+    process_in_order(
+        &files,
+        args.jobs,
+        |path| check_file(path, &args.directory, &registry, &format, &display_paths),
+        |_, checked| {
+            match checked {
+                Checked::Skipped => {}
+                Checked::Ok => report.files_checked += 1,
+                Checked::Violation(violation) => {
+                    report.files_checked += 1;
+                    report.violations.push(violation);
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    match check_args.format {
+        OutputFormat::Text => print_text(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    if !report.violations.is_empty() {
+        bail!("{} of {} file(s) have header problems", report.violations.len(), report.files_checked);
+    }
+    Ok(())
+}
+
+/// Checks one file's header.
+fn check_file(
+    path: &Path,
+    root: &Path,
+    registry: &LanguageRegistry,
+    format: &HeaderFormat,
+    display_paths: &DisplayPaths,
+) -> Result<Checked> {
+    let Ok(TextFile { content, .. }) = TextFile::read(path) else {
+        return Ok(Checked::Skipped);
+    };
+
+    let language = registry.language_for(path);
+    let display_path = display_paths.display_path(path)?;
+    let expected = format.render(language, path, &display_path);
+    let found = format.find(&content, language);
+
+    let problem = match &found {
+        None => Problem::Missing,
+        Some(header) if !header.parsed.has_style_of(language) => Problem::Malformed,
+        Some(header) if header.outdated => Problem::Outdated,
+        Some(header) if !header.parsed.points_to(&display_path) => Problem::Stale,
+        Some(_) => return Ok(Checked::Ok),
+    };
+    Ok(Checked::Violation(Violation {
+        file: relative_path(path, root),
+        problem,
+        expected,
+        found: found.map(|header| content[header.range].trim_end().to_string()),
+    }))
+}
+
+fn print_text(report: &Report) {
+    for violation in &report.violations {
+        println!("{:<12} {}", violation.problem.label(), violation.file);
+        if let Some(found) = &violation.found {
+            println!("             found:    {}", found);
+        }
+        println!("             expected: {}", violation.expected);
+    }
+    println!(
+        "\n'check' command finished: {} file(s) checked, {} problem(s) found.",
+        report.files_checked,
+        report.violations.len()
+    );
+}
+
+fn relative_path(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+}
@@ -1,200 +1,234 @@
 // src/commands/clean.rs
 
-use anyhow::Result;
-use std::fs;
- 
-
-use crate::cli::Args;
-use super::utils::{create_file_walker, resolve_extensions};
-
-/// Helper function to remove single-line and inline comments from a line,
-/// ensuring that comment markers within string literals are preserved.
-/// It works for single-line comment styles like `//` or `#`.
-fn clean_line_of_code(line: &str, comment_prefix: &str) -> String {
-    if comment_prefix.is_empty() {
-        return line.trim_end().to_string(); 
-    }
-
-    let mut in_single_quote = false;
-    let mut in_double_quote = false;
-    let mut idx = 0;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::cli::CleanArgs;
+use super::changes::{ChangeWriter, Outcome};
+use crate::header::{header_line_index, HeaderFormat};
+use crate::languages::{Language, LanguageRegistry};
+use crate::lexer::{self, Lexed, Unterminated};
+use crate::license::find_license;
+use crate::text::TextFile;
+use super::utils::{collect_files, FileSelector, load_header_format, load_registry, process_in_order};
 
-    while idx < line.len() {
-        let current_char_start_idx = idx;
-        let char_len = line[idx..].chars().next().map_or(0, |c| c.len_utf8());
-        if char_len == 0 { break; }
+/// Handles the 'clean' subcommand logic.
+pub fn clean(clean_args: &CleanArgs) -> Result<()> {
+    let args = &clean_args.args;
+    println!("Searching in: {:?}", &args.directory);
+    let registry = load_registry(&args.directory)?;
+    let format = load_header_format(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let files = collect_files(args, &selector)?;
+    let keep_patterns = compile_keep_patterns(&clean_args.keep_patterns)?;
+    let mut writer = ChangeWriter::new(&args.directory, args.dry_run).journaled("clean");
+
+    process_in_order(
+        &files,
+        args.jobs,
+        |path| clean_file(path, &registry, &format, clean_args, &keep_patterns),
+        |path, outcome| writer.apply(path, outcome),
+    )?;
+    writer.finish()?;
+    println!("\n'clean' command finished.");
+    Ok(())
+}
 
-        let c = line[idx..].chars().next().unwrap();
+/// Compiles the `--keep-pattern` regexes.
+pub(super) fn compile_keep_patterns(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).with_context(|| format!("Invalid --keep-pattern: {}", p)))
+        .collect()
+}
 
-        // Handle escaped characters
-        if c == '\\' {
-            idx += char_len; 
-            if idx < line.len() {
-                idx += line[idx..].chars().next().map_or(0, |c| c.len_utf8()); 
-            }
-            continue; 
-        }
+/// Whether `clean` keeps a comment with this body because tools act on it.
+pub(super) fn is_kept_directive(language: &Language, keep_patterns: &[Regex], body: &str) -> bool {
+    language.is_directive(body) || keep_patterns.iter().any(|pattern| pattern.is_match(body))
+}
 
-        // Toggle string state
-        if c == '\'' && !in_double_quote {
-            in_single_quote = !in_single_quote;
-        } else if c == '"' && !in_single_quote {
-            in_double_quote = !in_double_quote;
-        }
+/// The lines `clean` never touches: shebangs, encoding lines and the like, the path
+/// header and the license block.
+pub(super) struct KeptLines {
+    preamble_end: usize,
+    header_lines: Range<usize>,
+    license_lines: Range<usize>,
+    path_header_prefixes: [String; 2],
+}
 
-        // Check for comment prefix only if NOT inside a string literal
-        if !in_single_quote && !in_double_quote {
-            if line[current_char_start_idx..].starts_with(comment_prefix) {
-                return line[..current_char_start_idx].trim_end().to_string();
-            }
-        }
-        
-        idx += char_len; 
+impl KeptLines {
+    pub(super) fn find(original: &str, language: &Language, format: &HeaderFormat) -> Self {
+        let preamble_end = header_line_index(original, language);
+        let header_lines = format.find(original, language).map_or(preamble_end..preamble_end, |found| found.lines);
+        // The comment holding the license may be a whole `/* ... */` block.
+        let license_lines = find_license(original, language, header_lines.end).map_or(0..0, |block| block.lines);
+        let (line_prefix, block_start) = (
+            language.line_comment.as_deref().unwrap_or(""),
+            language.block_comment.as_ref().map_or("", |(start, _)| start.as_str()),
+        );
+        let path_header_prefixes = [format!("{} Path:", line_prefix), format!("{} Path:", block_start)];
+        KeptLines { preamble_end, header_lines, license_lines, path_header_prefixes }
     }
 
-    line.trim_end().to_string()
+    /// Whether line `line_num` (counting from 0), whose trimmed text is `trimmed_line`, is kept.
+    pub(super) fn contains(&self, line_num: usize, trimmed_line: &str) -> bool {
+        line_num < self.preamble_end
+            || self.header_lines.contains(&line_num)
+            || self.license_lines.contains(&line_num)
+            || self.path_header_prefixes.iter().any(|prefix| trimmed_line.starts_with(prefix.as_str()))
+    }
 }
 
-/// Handles the 'clean' subcommand logic.
-pub fn clean(args: &Args) -> Result<()> {
-    println!("Searching in: {:?}", &args.directory);
-    let extensions = resolve_extensions(args);
-    let walker = create_file_walker(&args.directory, &extensions, args.depth);
-
-    for entry in walker {
-        let path = entry.path();
-        let original_lines: Vec<String> =
-            fs::read_to_string(path)?.lines().map(String::from).collect();
-        let mut new_lines: Vec<String> = Vec::new();
-
-        // Determine specific comment styles for the current file extension
-        let (single_line_prefix_str, block_comment_start_str, block_comment_end_str) = {
-            let file_ext = path.extension().and_then(|s| s.to_str());
-            match file_ext {
-                Some("c" | "cpp" | "h" | "hpp" | "cs" | "go" | "java" | "rs" | "swift" | "kt" | "dart") => 
-                    ("//", "/*", "*/"),
-                Some("js" | "ts" | "jsx" | "tsx") => 
-                    ("//", "/*", "*/"),
-                Some("css" | "scss" | "less") => 
-                    ("", "/*", "*/"), // These only use block comments
-                Some("html" | "svelte" | "vue" | "xml" | "md") => 
-                    ("", "<!--", "-->"), // These only use HTML-style block comments
-                Some("py" | "rb" | "sh" | "bash" | "pl" | "Dockerfile" | "yaml" | "yml" | "toml" | "ps1") => 
-                    ("#", "", ""), // These only use single-line comments
-                _ => ("//", "", ""), // Default to C-style single-line if unknown
-            }
-        };
-
-        let path_header_prefix_single_line = format!("{} Path:", single_line_prefix_str);
-        let path_header_prefix_block_start = format!("{} Path:", block_comment_start_str);
+/// Works out the cleaned content of one file.
+fn clean_file(
+    path: &Path,
+    registry: &LanguageRegistry,
+    format: &HeaderFormat,
+    clean_args: &CleanArgs,
+    keep_patterns: &[Regex],
+) -> Result<Outcome> {
+    let Ok(file) = TextFile::read(path) else {
+        return Ok(Outcome::unchanged(format!("[SKIP] Not a UTF-8 text file: {}", path.display())));
+    };
+    let original = &file.content;
+    let language = registry.language_for(path);
+    let mut lexed = match lexer::lex(language, original) {
+        Ok(lexed) => lexed,
+        Err(unterminated) => return Ok(unterminated_outcome(path, &unterminated)),
+    };
+    // A language's own setting beats the command-wide one.
+    if language.keep_docs.unwrap_or(clean_args.keep_docs) {
+        lexed.comments.retain(|comment| !comment.doc);
+    }
+    // Comments that tools act on (`eslint-disable`, `# noqa`, ...) stay too, and are listed.
+    let mut preserved = Vec::new();
+    lexed.comments.retain(|comment| {
+        let body = comment.body(original, language);
+        let directive = is_kept_directive(language, keep_patterns, body);
+        if directive {
+            let text = original[comment.range.clone()].lines().next().unwrap_or_default();
+            preserved.push(format!("  [PRESERVED] {}:{}: {}", path.display(), comment.line(original), text));
+        }
+        !directive
+    });
+
+    // Shebangs, encoding lines, front matter, the header and the license are kept as they are.
+    let kept_lines = KeptLines::find(original, language, format);
+    let kept = |line_num: usize, trimmed_line: &str| kept_lines.contains(line_num, trimmed_line);
+
+    let mut cuts: Vec<Cut> = lexed.comments.iter().map(|comment| Cut { range: comment.range.clone(), replacement: "" }).collect();
+    if clean_args.strip_docstrings && !clean_args.keep_docstrings {
+        // A body can't be empty, so a docstring that is all there is becomes `pass`.
+        for docstring in &lexed.docstrings {
+            let replacement = if docstring.sole_statement { "pass" } else { "" };
+            cuts.push(Cut { range: docstring.range.clone(), replacement });
+        }
+        // Their lines are no longer inside a string.
+        lexed.strings.retain(|string| !lexed.docstrings.iter().any(|docstring| docstring.range == *string));
+        cuts.sort_by_key(|cut| cut.range.start);
+    }
 
-        let mut in_multi_line_block_comment = false; 
-        let mut in_python_triple_double_quote_string = false;
-        let mut in_python_triple_single_quote_string = false;
+    let new_lines = strip_comments(original, &lexed, &cuts, clean_args.collapse_blank_lines, kept);
+    Ok(outcome(file, path, &new_lines, &preserved))
+}
 
-        let is_python = path.extension().and_then(|s| s.to_str()) == Some("py");
+/// A span of the file to remove, and what to put in its place.
+struct Cut {
+    range: Range<usize>,
+    replacement: &'static str,
+}
 
-        for line_num in 0..original_lines.len() {
-            let line = &original_lines[line_num];
-            let trimmed_line = line.trim();
-            let mut current_processed_line_content = String::new(); 
-            let mut remaining_line_segment = line.as_str(); 
+/// Leaves a file with an unterminated comment alone: cleaning it would delete the rest of the file.
+fn unterminated_outcome(path: &Path, unterminated: &Unterminated) -> Outcome {
+    Outcome::unchanged(format!("[UNTERMINATED] Not cleaning, {}: {}", unterminated, path.display()))
+}
 
-            // 1. Path header always stays
-            if trimmed_line.starts_with(&path_header_prefix_single_line) || trimmed_line.starts_with(&path_header_prefix_block_start) {
-                new_lines.push(line.clone());
-                continue;
+/// Makes the `cuts` (sorted by start), except on kept lines. Lines without a cut are left exactly
+/// as they are, and so are the lines of a multi-line string. Lines a cut leaves empty are dropped,
+/// and runs of blank lines are capped at `collapse_blank_lines`, if given.
+fn strip_comments(
+    original: &str,
+    lexed: &Lexed,
+    cuts: &[Cut],
+    collapse_blank_lines: Option<usize>,
+    kept: impl Fn(usize, &str) -> bool,
+) -> Vec<String> {
+    let mut new_lines: Vec<String> = Vec::new();
+    let mut cuts = cuts.iter().peekable();
+    let mut line_start = 0;
+
+    for (line_num, raw_line) in original.split_inclusive('\n').enumerate() {
+        let line = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line_end = line_start + line.len();
+
+        // Cuts that ended on an earlier line are done with.
+        while cuts.peek().is_some_and(|cut| cut.range.end <= line_start) {
+            cuts.next();
+        }
+        let mut on_line = cuts.clone().take_while(|cut| cut.range.start < line_end).peekable();
+        if kept(line_num, line.trim()) {
+            new_lines.push(line.to_string());
+        } else if on_line.peek().is_none() {
+            // Blank lines that never held a comment stay, up to the cap.
+            let blank_run = new_lines.iter().rev().take_while(|line| line.trim().is_empty()).count();
+            if !line.trim().is_empty() || lexed.in_string(line_end) || collapse_blank_lines.is_none_or(|max| blank_run < max) {
+                new_lines.push(line.to_string());
             }
-
-            // --- Python Triple-Quoted String/Docstring Handling ---
-            if is_python {
-                let num_triple_double = line.matches("\"\"\"").count();
-                let num_triple_single = line.matches("'''").count();
-
-                let was_in_multiline_string = in_python_triple_double_quote_string || in_python_triple_single_quote_string;
-
-                // Toggle state if an odd number of delimiters is found
-                if num_triple_double % 2 != 0 {
-                    in_python_triple_double_quote_string = !in_python_triple_double_quote_string;
-                }
-                if num_triple_single % 2 != 0 {
-                    in_python_triple_single_quote_string = !in_python_triple_single_quote_string;
-                }
-
-                // Preserve the line if it was or is part of a multiline string/docstring
-                if was_in_multiline_string || num_triple_double > 0 || num_triple_single > 0 {
-                    new_lines.push(line.clone());
-                    continue;
+        } else {
+            let mut cleaned = String::new();
+            let mut pos = line_start;
+            for cut in on_line {
+                if cut.range.start > pos {
+                    cleaned.push_str(&original[pos..cut.range.start]);
                 }
-            }
-            
-            // --- Block Comment Handling (e.g., /* ... */, <!-- ... -->) ---
-            if !block_comment_start_str.is_empty() && !block_comment_end_str.is_empty() {
-                // If currently inside a multi-line block comment
-                if in_multi_line_block_comment {
-                    if let Some(end_idx) = remaining_line_segment.find(block_comment_end_str) {
-                        current_processed_line_content.push_str(&remaining_line_segment[end_idx + block_comment_end_str.len()..]);
-                        remaining_line_segment = ""; 
-                        in_multi_line_block_comment = false;
-                    } else {
-                        continue; // Entire line is part of an ongoing multi-line block comment, skip it
-                    }
+                // A cut spanning lines is replaced on its first line only.
+                if cut.range.start >= line_start {
+                    cleaned.push_str(cut.replacement);
                 }
-
-                // Process for any block comments (inline or new multi-line starts)
-                while let Some(start_idx) = remaining_line_segment.find(block_comment_start_str) {
-                    current_processed_line_content.push_str(&remaining_line_segment[..start_idx]); 
-                    remaining_line_segment = &remaining_line_segment[start_idx + block_comment_start_str.len()..]; 
-
-                    if let Some(end_idx) = remaining_line_segment.find(block_comment_end_str) {
-                        remaining_line_segment = &remaining_line_segment[end_idx + block_comment_end_str.len()..];
-                    } else {
-                        in_multi_line_block_comment = true;
-                        remaining_line_segment = ""; 
-                        break; 
+                pos = pos.max(cut.range.end);
+                // Code after a leading comment keeps the line's indentation, not the comment's spacing.
+                if cleaned.trim().is_empty() {
+                    while pos < line_end && matches!(original.as_bytes()[pos], b' ' | b'\t') {
+                        pos += 1;
                     }
                 }
             }
-            current_processed_line_content.push_str(remaining_line_segment); 
-
-            // --- Single-line Comment Handling (// or #) ---
-            // This applies to any remaining content after docstrings and block comments.
-            if !single_line_prefix_str.is_empty() { 
-                let cleaned_single_line = clean_line_of_code(&current_processed_line_content, single_line_prefix_str);
-                if !cleaned_single_line.is_empty() {
-                    new_lines.push(cleaned_single_line);
-                }
-            } else {
-                // If no single-line prefix for this language (e.g., HTML, CSS),
-                // just push remaining content after block comment processing.
-                let trimmed_final = current_processed_line_content.trim_end().to_string();
-                if !trimmed_final.is_empty() {
-                    new_lines.push(trimmed_final);
-                }
+            if pos < line_end {
+                cleaned.push_str(&original[pos..line_end]);
+            }
+            if lexed.in_string(line_end) {
+                new_lines.push(cleaned);
+            } else if !cleaned.trim().is_empty() {
+                new_lines.push(cleaned.trim_end().to_string());
             }
         }
+        line_start += raw_line.len();
+    }
+    new_lines
+}
+
+/// Compares the cleaned lines with the original ones. The `preserved` comments are listed after the file.
+fn outcome(file: TextFile, path: &Path, new_lines: &[String], preserved: &[String]) -> Outcome {
+    let new_content_str = new_lines.join("\n");
+    let original_content_str = file.content.lines().collect::<Vec<_>>().join("\n");
+    
+    let new_content_normalized = new_content_str.trim_end_matches('\n').to_string();
+    let original_content_normalized = original_content_str.trim_end_matches('\n').to_string();
 
-        // Final content comparison and write
-        let new_content_str = new_lines.join("\n");
-        let original_content_str = original_lines.join("\n");
-        
-        let new_content_normalized = new_content_str.replace("\r\n", "\n").trim_end_matches('\n').to_string();
-        let original_content_normalized = original_content_str.replace("\r\n", "\n").trim_end_matches('\n').to_string();
-
-        let has_changed = new_content_normalized != original_content_normalized;
-
-        if has_changed {
-            let final_content = if new_content_normalized.is_empty() {
-                "".to_string()
-            } else {
-                format!("{}\n", new_content_normalized)
-            };
-            fs::write(path, final_content)?;
-            println!("[CLEANED] Comments from: {}", path.display());
+    let has_changed = new_content_normalized != original_content_normalized;
+    let report = |message: String| std::iter::once(message).chain(preserved.iter().cloned()).collect::<Vec<_>>().join("\n");
+
+    if has_changed {
+        let final_content = if new_content_normalized.is_empty() {
+            "".to_string()
         } else {
-            println!("[SKIP] No comments to clean: {}", path.display());
-        }
+            format!("{}\n", new_content_normalized)
+        };
+        Outcome::edited(file, &final_content, report(format!("[CLEANED] Comments from: {}", path.display())))
+    } else {
+        Outcome::unchanged(report(format!("[SKIP] No comments to clean: {}", path.display())))
     }
-    println!("\n'clean' command finished.");
-    Ok(())
-}
\ No newline at end of file
+}
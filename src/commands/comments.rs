@@ -0,0 +1,191 @@
+// src/commands/comments.rs
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::cli::{CommentsArgs, ExportFormat};
+use crate::header::HeaderFormat;
+use crate::languages::LanguageRegistry;
+use crate::lexer::{self, CommentKind};
+use crate::text::TextFile;
+use super::clean::{compile_keep_patterns, is_kept_directive, KeptLines};
+use super::utils::{collect_files, load_header_format, load_registry, process_in_order, FileSelector};
+
+/// What sort of comment an exported comment is. A directive that is also a doc comment is a directive.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Line,
+    Block,
+    /// A doc comment, or a Python docstring.
+    Doc,
+    /// A comment that tools act on, which `clean` keeps.
+    Directive,
+}
+
+impl Kind {
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Line => "line",
+            Kind::Block => "block",
+            Kind::Doc => "doc",
+            Kind::Directive => "directive",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct ExportedComment {
+    file: String,
+    start_line: usize,
+    end_line: usize,
+    kind: Kind,
+    /// The comment without its delimiters.
+    text: String,
+}
+
+/// Handles the 'comments' subcommand logic: exports every comment `clean` would see. Never modifies files.
+pub fn comments(comments_args: &CommentsArgs) -> Result<()> {
+    let args = &comments_args.args;
+    let registry = load_registry(&args.directory)?;
+    let format = load_header_format(&args.directory)?;
+    let keep_patterns = compile_keep_patterns(&comments_args.keep_patterns)?;
+    let selector = FileSelector::new(args, &registry);
+    let files = collect_files(args, &selector)?;
+    let filters = comments_args
+        .filter
+        .iter()
+        .map(|f| Regex::new(f).with_context(|| format!("Invalid --filter: {}", f)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut exported = Vec::new();
+    process_in_order(
+        &files,
+        args.jobs,
+        |path| file_comments(path, &args.directory, &registry, &format, &keep_patterns),
+        |_, found| {
+            let matching = found.into_iter().filter(|c| filters.is_empty() || filters.iter().any(|f| f.is_match(&c.text)));
+            exported.extend(matching);
+            Ok(())
+        },
+    )?;
+
+    let output = match comments_args.format {
+        ExportFormat::Json => format!("{}\n", serde_json::to_string_pretty(&exported)?),
+        ExportFormat::Csv => to_csv(&exported),
+    };
+    match &args.output {
+        Some(path) => {
+            fs::write(path, output).with_context(|| format!("Failed to write comments to {}", path.display()))?;
+            eprintln!("Wrote {} comment(s) to {}", exported.len(), path.display());
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+/// Finds the comments of one file with the same lexer and directive rules as `clean`.
+/// Comments on lines `clean` never touches, like the path header and the license block,
+/// are left out. Files that aren't text, or hold an unterminated comment or string, have none.
+fn file_comments(
+    path: &Path,
+    root: &Path,
+    registry: &LanguageRegistry,
+    format: &HeaderFormat,
+    keep_patterns: &[Regex],
+) -> Result<Vec<ExportedComment>> {
+    let Ok(TextFile { content, .. }) = TextFile::read(path) else {
+        return Ok(Vec::new());
+    };
+    let language = registry.language_for(path);
+    let lexed = match lexer::lex(language, &content) {
+        Ok(lexed) => lexed,
+        Err(unterminated) => {
+            // Stdout holds the export, so this goes to stderr.
+            eprintln!("[UNTERMINATED] Skipping, {}: {}", unterminated, path.display());
+            return Ok(Vec::new());
+        }
+    };
+
+    let file = path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+    let export = |range: &Range<usize>, kind: Kind, text: &str| {
+        let start_line = content[..range.start].matches('\n').count() + 1;
+        let end_line = start_line + content[range.clone()].matches('\n').count();
+        ExportedComment { file: file.clone(), start_line, end_line, kind, text: text.to_string() }
+    };
+
+    let kept_lines = KeptLines::find(&content, language, format);
+    let lines: Vec<&str> = content.lines().collect();
+    let mut found: Vec<(usize, ExportedComment)> = lexed
+        .comments
+        .iter()
+        .filter(|comment| {
+            let line_num = comment.line(&content) - 1;
+            !kept_lines.contains(line_num, lines.get(line_num).map_or("", |line| line.trim()))
+        })
+        .map(|comment| {
+            let body = comment.body(&content, language);
+            let kind = match comment.kind {
+                _ if is_kept_directive(language, keep_patterns, body) => Kind::Directive,
+                _ if comment.doc => Kind::Doc,
+                CommentKind::Line => Kind::Line,
+                CommentKind::Block => Kind::Block,
+            };
+            (comment.range.start, export(&comment.range, kind, body))
+        })
+        .collect();
+    for docstring in &lexed.docstrings {
+        let text = content[docstring.range.clone()].trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        let text = text.trim_matches(|c| c == '"' || c == '\'').trim();
+        found.push((docstring.range.start, export(&docstring.range, Kind::Doc, text)));
+    }
+    found.sort_by_key(|(start, _)| *start);
+    Ok(found.into_iter().map(|(_, comment)| comment).collect())
+}
+
+/// Writes the comments as CSV with a header row. Fields with commas, quotes or newlines are quoted.
+fn to_csv(comments: &[ExportedComment]) -> String {
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let mut csv = String::from("file,start_line,end_line,kind,text\n");
+    for comment in comments {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            field(&comment.file),
+            comment.start_line,
+            comment.end_line,
+            comment.kind.label(),
+            field(&comment.text)
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quotes_fields_that_need_it() {
+        let comment = |text: &str| ExportedComment {
+            file: "src/a,b.rs".to_string(),
+            start_line: 1,
+            end_line: 2,
+            kind: Kind::Block,
+            text: text.to_string(),
+        };
+        assert_eq!(
+            to_csv(&[comment("plain"), comment("say \"hi\"\nthere")]),
+            "file,start_line,end_line,kind,text\n\"src/a,b.rs\",1,2,block,plain\n\"src/a,b.rs\",1,2,block,\"say \"\"hi\"\"\nthere\"\n"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+// src/commands/config.rs
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use crate::cli::ConfigCommand;
+use crate::config::{ConfigLayers, Origin, Source};
+use crate::header::DEFAULT_TEMPLATE;
+use crate::license::DEFAULT_LICENSE_TEMPLATE;
+
+/// Handles the 'config' subcommand logic.
+pub fn config(command: &ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Show { directory } => show(directory.as_deref().unwrap_or(Path::new("."))),
+    }
+}
+
+/// Prints the merged configuration for `dir` and where each value came from.
+fn show(dir: &Path) -> Result<()> {
+    let layers = ConfigLayers::load(dir)?;
+
+    println!("Config layers (lowest priority first):");
+    let mut any_layer = false;
+    for source in layers.sources() {
+        println!("  {}", source);
+        any_layer = true;
+    }
+    if !any_layer {
+        println!("  (none, using built-in defaults)");
+    }
+
+    for command in ["add", "remove", "clean", "copy", "check", "comments", "license", "sync", "watch"] {
+        let (resolved, origins) = layers.resolve_args(command);
+        println!("\n[{}]", command);
+        print_field("project", resolved.project.as_ref().map(|p| format!("{:?}", p).to_lowercase()), "<none>", &origins);
+        print_field("exts", resolved.exts.as_ref().map(|e| e.join(",")), "<all supported>", &origins);
+        print_field("up", resolved.up, 0, &origins);
+        print_field("depth", resolved.depth, "<unlimited>", &origins);
+        print_field("force", resolved.force, false, &origins);
+        print_field("output", resolved.output.as_ref().map(|p| p.display().to_string()), "<clipboard>", &origins);
+        print_field("no_ignore", resolved.no_ignore, false, &origins);
+        print_field("include", resolved.include.as_ref().map(|g| g.join(",")), "<everything>", &origins);
+        print_field("exclude", resolved.exclude.as_ref().map(|g| g.join(",")), "<nothing>", &origins);
+        print_field("jobs", resolved.jobs, "<cpus>", &origins);
+        if command == "clean" {
+            print_field("keep_docs", resolved.keep_docs, false, &origins);
+            print_field("keep_patterns", resolved.keep_patterns.as_ref().map(|p| format!("{:?}", p)), "[]", &origins);
+            print_field("strip_docstrings", resolved.strip_docstrings, false, &origins);
+            print_field("collapse_blank_lines", resolved.collapse_blank_lines, "<off>", &origins);
+        }
+        if command == "license" {
+            let (license, origins) = layers.resolve_license();
+            print_field("spdx", license.spdx.as_ref(), "<none>", &origins);
+            print_field("holder", license.holder.as_ref(), "<none>", &origins);
+            print_field("template", license.template.as_ref().map(|t| format!("{:?}", t)), format!("{:?}", DEFAULT_LICENSE_TEMPLATE), &origins);
+        }
+    }
+
+    let (structure, origins) = layers.resolve_structure();
+    println!("\n[structure]");
+    print_field("file", structure.file.as_ref().map(|p| p.display().to_string()), "<stdin>", &origins);
+    print_field("directory", structure.directory.as_ref().map(|p| p.display().to_string()), ".", &origins);
+    print_field("indent", structure.indent, 4, &origins);
+
+    let (header, origins) = layers.resolve_header();
+    println!("\n[header]");
+    print_field("template", header.template.as_ref().map(|t| format!("{:?}", t)), format!("{:?}", DEFAULT_TEMPLATE), &origins);
+    print_field("legacy_templates", header.legacy_templates.as_ref().map(|t| format!("{:?}", t)), "[]", &origins);
+    print_field("blank_line", header.blank_line, false, &origins);
+
+    let languages = layers.languages();
+    if !languages.is_empty() {
+        println!("\n[languages]");
+        for (name, (_, source)) in &languages {
+            println!("  {:<10} ({})", name, source);
+        }
+    }
+    Ok(())
+}
+
+fn print_field<T: Display, D: Display>(
+    name: &str,
+    value: Option<T>,
+    default: D,
+    origins: &BTreeMap<&'static str, Origin>,
+) {
+    let (value, origin) = match (value, origins.get(name)) {
+        (Some(value), Some(origin)) => (value.to_string(), origin.to_string()),
+        _ => (default.to_string(), Source::Default.to_string()),
+    };
+    println!("  {:<10} = {:<20} ({})", name, value, origin);
+}
@@ -4,23 +4,43 @@ use anyhow::{Context, Result};
 use arboard::Clipboard;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::cli::Args;
-use super::utils::{create_file_walker, generate_display_path, resolve_extensions};
+use super::utils::{collect_files, load_registry, process_in_order, DisplayPaths, FileSelector};
+
+/// Reads one file for the bundle, returning its header path and content.
+fn read_entry(path: &Path, display_paths: &DisplayPaths) -> Result<(PathBuf, String)> {
+    let display_path = display_paths.display_path(path)?;
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok((display_path, content))
+}
 
 /// Handles the 'copy' subcommand logic.
 pub fn copy(args: &Args) -> Result<()> {
     println!("Searching for files to copy in: {:?}", &args.directory);
-    let extensions = resolve_extensions(args);
-    let walker = create_file_walker(&args.directory, &extensions, args.depth);
-
-    let mut paths_to_copy: Vec<PathBuf> = walker.map(|e| e.path().to_path_buf()).collect();
+    let registry = load_registry(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let mut paths_to_copy = collect_files(args, &selector)?;
+    // An earlier bundle inside the tree must not end up in the new one.
+    if let Some(output) = args.output.as_ref().and_then(|p| p.canonicalize().ok()) {
+        paths_to_copy.retain(|path| path.canonicalize().ok().as_ref() != Some(&output));
+    }
     if paths_to_copy.is_empty() {
         println!("No files found matching the criteria.");
         return Ok(());
     }
     paths_to_copy.sort();
+    let display_paths = DisplayPaths::new(&args.directory, args.up)?;
+
+    if args.dry_run {
+        for path in &paths_to_copy {
+            println!("[WOULD COPY] {}", display_paths.display_path(path)?.display());
+        }
+        println!("\nDry run: {} file(s) would be copied. Nothing was written.", paths_to_copy.len());
+        return Ok(());
+    }
 
     let mut total_bytes = 0;
 
@@ -31,20 +51,21 @@ pub fn copy(args: &Args) -> Result<()> {
         let mut file = fs::File::create(output_path)
             .with_context(|| format!("Failed to create output file: {}", output_path.display()))?;
 
-        for (i, path) in paths_to_copy.iter().enumerate() {
+        // Files are read in parallel but written in sorted order, so the bundle is the same as a serial run.
+        let read = |path: &Path| read_entry(path, &display_paths);
+        let mut i = 0;
+        process_in_order(&paths_to_copy, args.jobs, read, |path, (display_path, content)| {
             println!("[PROCESSING] {}", path.display());
-            let display_path = generate_display_path(path, &args.directory, args.up)?;
-            let content = fs::read_to_string(path)
-                .with_context(|| format!("Failed to read file: {}", path.display()))?;
-
             total_bytes += content.len();
 
             if i > 0 {
                 write!(file, "\n\n---\n")?;
             }
+            i += 1;
             write!(file, "FILE: {}\n---\n\n", display_path.to_string_lossy())?;
             file.write_all(content.as_bytes())?;
-        }
+            Ok(())
+        })?;
         
         println!(
             "\n✅ Successfully wrote {} files ({} bytes) to the output file.",
@@ -57,20 +78,20 @@ pub fn copy(args: &Args) -> Result<()> {
         // No --output flag, so we build a single large string in memory for the clipboard.
         let mut combined_content = String::new();
 
-        for (i, path) in paths_to_copy.iter().enumerate() {
+        let read = |path: &Path| read_entry(path, &display_paths);
+        let mut i = 0;
+        process_in_order(&paths_to_copy, args.jobs, read, |path, (display_path, content)| {
             println!("[PROCESSING] {}", path.display());
-            let display_path = generate_display_path(path, &args.directory, args.up)?;
-            let content = fs::read_to_string(path)
-                .with_context(|| format!("Failed to read file: {}", path.display()))?;
-
             total_bytes += content.len();
 
             if i > 0 {
                 combined_content.push_str("\n\n---\n");
             }
+            i += 1;
             combined_content.push_str(&format!("FILE: {}\n---\n\n", display_path.to_string_lossy()));
             combined_content.push_str(&content);
-        }
+            Ok(())
+        })?;
 
         let mut clipboard = Clipboard::new().context("Failed to initialize clipboard")?;
         clipboard.set_text(combined_content)
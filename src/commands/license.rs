@@ -0,0 +1,85 @@
+// src/commands/license.rs
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::cli::LicenseArgs;
+use crate::config::Config;
+use crate::header::{header_line_index, HeaderFormat};
+use crate::languages::LanguageRegistry;
+use crate::license::{LicenseChange, LicenseSpec};
+use crate::text::TextFile;
+use super::changes::{ChangeWriter, Outcome};
+use super::utils::{collect_files, load_registry, process_in_order, FileSelector};
+
+/// Handles the 'license' subcommand logic.
+pub fn license(license_args: &LicenseArgs) -> Result<()> {
+    let args = &license_args.args;
+    println!("Searching in: {:?}", &args.directory);
+    let config = Config::load(&args.directory)?;
+    let spdx = license_args.spdx.as_ref().or(config.license.spdx.as_ref())
+        .context("No license given: pass --spdx or set `spdx` in the [license] config section")?;
+    let holder = license_args.holder.as_ref().or(config.license.holder.as_ref())
+        .context("No copyright holder given: pass --holder or set `holder` in the [license] config section")?;
+    let spec = LicenseSpec::new(spdx, holder, config.license.template.as_deref())?;
+    let format = HeaderFormat::from_config(&config.header)?;
+
+    let registry = load_registry(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let files = collect_files(args, &selector)?;
+    let mut writer = ChangeWriter::new(&args.directory, args.dry_run).journaled("license");
+    let mut problems = 0;
+
+    process_in_order(
+        &files,
+        args.jobs,
+        |path| Ok(license_file(path, &registry, &format, &spec)),
+        |path, change| {
+            match change {
+                None => {}
+                Some((_, LicenseChange::UpToDate)) => println!("[SKIP] License up to date: {}", path.display()),
+                Some((_, LicenseChange::Added(_))) if license_args.check => {
+                    problems += 1;
+                    println!("[MISSING] License in: {}", path.display());
+                }
+                Some((_, LicenseChange::Updated(_))) if license_args.check => {
+                    problems += 1;
+                    println!("[OUTDATED] License in: {}", path.display());
+                }
+                Some((file, LicenseChange::Added(new))) => {
+                    writer.apply(path, Outcome::edited(file, &new, format!("[ADDED] License in: {}", path.display())))?
+                }
+                Some((file, LicenseChange::Updated(new))) => {
+                    writer.apply(path, Outcome::edited(file, &new, format!("[UPDATED] License in: {}", path.display())))?
+                }
+            }
+            Ok(())
+        },
+    )?;
+    writer.finish()?;
+    println!("\n'license' command finished.");
+
+    if problems > 0 {
+        bail!("{} of {} file(s) have a missing or outdated license", problems, files.len());
+    }
+    Ok(())
+}
+
+/// Works out the license change for one file, returning it with the file it applies to.
+/// Returns `None` for files that can't be read as text.
+fn license_file(
+    path: &Path,
+    registry: &LanguageRegistry,
+    format: &HeaderFormat,
+    spec: &LicenseSpec,
+) -> Option<(TextFile, LicenseChange)> {
+    let file = TextFile::read(path).ok()?;
+    let content = &file.content;
+    let language = registry.language_for(path);
+    // The license goes right below the path header, so neither gets in the way of the other.
+    let start = format
+        .find(content, language)
+        .map_or_else(|| header_line_index(content, language), |found| found.lines.end);
+    let change = spec.apply(content, language, start);
+    Some((file, change))
+}
@@ -6,11 +6,19 @@ use crate::cli::Commands;
 // Declare all the public sub-modules for our commands.
 mod add;
 mod remove;
+mod check;
+mod comments;
+mod license;
+mod sync;
+mod watch;
 mod clean;
+mod config;
 mod copy;
 mod structure;
+mod undo;
 
-// Declare a private module for shared helper functions.
+// Declare private modules for shared helper functions.
+mod changes;
 mod utils;
 
 /// The main dispatcher function. It receives a command from the CLI
@@ -19,9 +27,17 @@ pub fn handle_command(command: &Commands) -> Result<()> {
     match command {
         Commands::Add(args) => add::add(args)?,
         Commands::Remove(args) => remove::remove(args)?,
-        Commands::Clean(args) => clean::clean(args)?,
+        Commands::Clean(clean_args) => clean::clean(clean_args)?,
         Commands::Copy(args) => copy::copy(args)?,
+        Commands::Check(args) => check::check(args)?,
+        Commands::Comments(args) => comments::comments(args)?,
+        Commands::License(args) => license::license(args)?,
+        Commands::Sync(args) => sync::sync(args)?,
+        Commands::Watch(args) => watch::watch(args)?,
         Commands::Structure(args) => structure::structure(args)?,
+        Commands::Undo(args) => undo::undo(args)?,
+        Commands::History(args) => undo::history(args)?,
+        Commands::Config(command) => config::config(command)?,
     }
     Ok(())
 }
\ No newline at end of file
@@ -1,35 +1,41 @@
 // src/commands/remove.rs
 
 use anyhow::Result;
-use std::fs;
-use std::io::{BufRead, BufReader};
+use std::path::Path;
 
 use crate::cli::Args;
-use crate::file_utils::get_comment_style;
-use super::utils::{create_file_walker, resolve_extensions}; // THESE IMPORTS MUST BE PRESENT
+use crate::header::HeaderFormat;
+use crate::languages::LanguageRegistry;
+use crate::text::TextFile;
+use super::changes::{ChangeWriter, Outcome};
+use super::utils::{collect_files, load_header_format, load_registry, process_in_order, FileSelector};
 
 pub fn remove(args: &Args) -> Result<()> {
     println!("Searching in: {:?}", &args.directory);
-    let extensions = resolve_extensions(args);
-    let walker = create_file_walker(&args.directory, &extensions, args.depth);
+    let registry = load_registry(&args.directory)?;
+    let format = load_header_format(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let files = collect_files(args, &selector)?;
+    let mut writer = ChangeWriter::new(&args.directory, args.dry_run).journaled("remove");
 
-    for entry in walker {
-        let path = entry.path();
-        let mut first_line = String::new();
-        if fs::File::open(path).and_then(|f| BufReader::new(f).read_line(&mut first_line)).is_err() {
-            continue;
-        }
-
-        let (prefix, _) = get_comment_style(path);
-        if first_line.trim().starts_with(&format!("{} Path:", prefix)) {
-            let content = fs::read_to_string(path)?;
-            let new_content: String = content.lines().skip(1).collect::<Vec<&str>>().join("\n");
-            fs::write(path, new_content)?;
-            println!("[REMOVED] Header from: {}", path.display());
-        } else {
-            println!("[SKIP] No header found: {}", path.display());
-        }
-    }
+    process_in_order(&files, args.jobs, |path| Ok(remove_header(path, &registry, &format)), |path, outcome| match outcome {
+        Some(outcome) => writer.apply(path, outcome),
+        None => Ok(()),
+    })?;
+    writer.finish()?;
     println!("\n'remove' command finished.");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Works out the new content of one file. Returns `None` for files that can't be read as text.
+fn remove_header(path: &Path, registry: &LanguageRegistry, format: &HeaderFormat) -> Option<Outcome> {
+    let file = TextFile::read(path).ok()?;
+    let content = &file.content;
+    Some(match format.find(content, registry.language_for(path)) {
+        Some(found) => {
+            let new_content = format!("{}{}", &content[..found.range.start], &content[found.range.end..]);
+            Outcome::edited(file, &new_content, format!("[REMOVED] Header from: {}", path.display()))
+        }
+        None => Outcome::unchanged(format!("[SKIP] No header found: {}", path.display())),
+    })
+}
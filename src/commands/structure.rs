@@ -6,6 +6,7 @@ use std::io::{self, BufRead};
 use std::path::{Path, PathBuf};
 
 use crate::cli::StructureArgs;
+use super::changes::ChangeWriter;
 
 /// Represents a file or directory in the structure tree.
 #[derive(Debug)]
@@ -96,27 +97,25 @@ fn build_tree(lines: Vec<String>, indent_width: u32) -> Node {
 }
 
 /// Recursively traverses the Node tree and creates the file/folder structure on disk.
-fn create_structure_from_tree(node: &Node, base_path: &Path) -> Result<()> {
+fn create_structure_from_tree(node: &Node, base_path: &Path, writer: &mut ChangeWriter) -> Result<()> {
     for child in &node.children {
         let full_path = base_path.join(&child.relative_path);
         let is_dir = child.name.ends_with('/') || !child.children.is_empty();
 
         if is_dir {
-            fs::create_dir_all(&full_path)
-                .with_context(|| format!("Failed to create directory: {:?}", full_path))?;
-            println!("[CREATED DIR]  {}", full_path.display());
+            writer.create_dir(&full_path)?;
+            if !writer.is_dry_run() {
+                println!("[CREATED DIR]  {}", full_path.display());
+            }
         } else {
-            if let Some(parent_dir) = full_path.parent() {
-                fs::create_dir_all(parent_dir)
-                    .with_context(|| format!("Failed to create parent directory for file: {:?}", full_path))?;
+            writer.create_file(&full_path)?;
+            if !writer.is_dry_run() {
+                println!("[CREATED FILE] {}", full_path.display());
             }
-            fs::File::create(&full_path)
-                .with_context(|| format!("Failed to create file: {:?}", full_path))?;
-            println!("[CREATED FILE] {}", full_path.display());
         }
 
         if !child.children.is_empty() {
-            create_structure_from_tree(child, base_path)?;
+            create_structure_from_tree(child, base_path, writer)?;
         }
     }
     Ok(())
@@ -138,16 +137,24 @@ pub fn structure(args: &StructureArgs) -> Result<()> {
     };
 
     let output_dir = args.directory.clone().unwrap_or_else(|| PathBuf::from("."));
-    fs::create_dir_all(&output_dir)
-        .with_context(|| format!("Failed to create base directory: {:?}", output_dir))?;
-    let absolute_output_dir = output_dir.canonicalize()?;
+    let mut writer = ChangeWriter::new(&output_dir, args.dry_run).journaled("structure");
+    let absolute_output_dir = if args.dry_run && !output_dir.exists() {
+        output_dir.clone()
+    } else {
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create base directory: {:?}", output_dir))?;
+        output_dir.canonicalize()?
+    };
 
     println!("Building structure in: {}", absolute_output_dir.display());
 
     let tree = build_tree(lines, args.indent);
     
-    create_structure_from_tree(&tree, &absolute_output_dir)?;
+    create_structure_from_tree(&tree, &absolute_output_dir, &mut writer)?;
 
-    println!("\n✅ Structure created successfully.");
+    if !writer.is_dry_run() {
+        println!("\n✅ Structure created successfully.");
+    }
+    writer.finish()?;
     Ok(())
 }
\ No newline at end of file
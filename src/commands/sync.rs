@@ -0,0 +1,119 @@
+// src/commands/sync.rs
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cli::SyncArgs;
+use crate::header::HeaderFormat;
+use crate::languages::LanguageRegistry;
+use crate::text::TextFile;
+use super::changes::{ChangeWriter, Outcome};
+use super::utils::{collect_files, load_header_format, load_registry, process_in_order, DisplayPaths, FileSelector};
+
+/// What `sync` found in one file.
+pub(super) enum Status {
+    NoHeader,
+    UpToDate,
+    Stale(Outcome),
+}
+
+/// Handles the 'sync' subcommand logic. Rewrites only the headers that point at an old path.
+pub fn sync(sync_args: &SyncArgs) -> Result<()> {
+    let args = &sync_args.args;
+    println!("Searching in: {:?}", &args.directory);
+    let registry = load_registry(&args.directory)?;
+    let format = load_header_format(&args.directory)?;
+    let selector = FileSelector::new(args, &registry);
+    let mut files = collect_files(args, &selector)?;
+    if let Some(rev) = &sync_args.git_renames {
+        let renamed = git_renamed_files(&args.directory, rev)?;
+        files.retain(|file| file.canonicalize().is_ok_and(|file| renamed.contains(&file)));
+        println!("Limiting to {} file(s) renamed since {}.", files.len(), rev);
+    }
+    let display_paths = DisplayPaths::new(&args.directory, args.up)?;
+    let mut writer = ChangeWriter::new(&args.directory, args.dry_run).journaled("sync");
+    let (mut synced, mut up_to_date, mut without_header) = (0, 0, 0);
+
+    process_in_order(
+        &files,
+        args.jobs,
+        |path| sync_file(path, &registry, &format, &display_paths),
+        |path, status| {
+            match status {
+                None => {}
+                Some(Status::NoHeader) => without_header += 1,
+                Some(Status::UpToDate) => up_to_date += 1,
+                Some(Status::Stale(outcome)) => {
+                    synced += 1;
+                    writer.apply(path, outcome)?;
+                }
+            }
+            Ok(())
+        },
+    )?;
+    writer.finish()?;
+    println!(
+        "\n'sync' command finished: {} header(s) updated, {} already up to date, {} file(s) without a header.",
+        synced, up_to_date, without_header
+    );
+    Ok(())
+}
+
+/// Works out whether one file's header is stale. Returns `None` for files that can't be read as text.
+pub(super) fn sync_file(
+    path: &Path,
+    registry: &LanguageRegistry,
+    format: &HeaderFormat,
+    display_paths: &DisplayPaths,
+) -> Result<Option<Status>> {
+    let Ok(file) = TextFile::read(path) else {
+        return Ok(None);
+    };
+    let content = &file.content;
+    let language = registry.language_for(path);
+    let Some(found) = format.find(content, language) else {
+        return Ok(Some(Status::NoHeader));
+    };
+    let display_path = display_paths.display_path(path)?;
+    if found.parsed.points_to(&display_path) {
+        return Ok(Some(Status::UpToDate));
+    }
+
+    let header = format.render_block(language, path, &display_path);
+    let new_content = format!("{}{}{}", &content[..found.range.start], header, &content[found.range.end..]);
+    let fields = &found.parsed.fields;
+    let old = ["path", "filename", "module"].iter().find_map(|name| fields.get(*name)).map_or("?", |v| v.as_str());
+    let message = format!("[SYNCED] {} → {}: {}", old, display_path.display(), path.display());
+    Ok(Some(Status::Stale(Outcome::edited(file, &new_content, message))))
+}
+
+/// The canonical paths of files under `dir` that git reports as renamed or copied since `rev`.
+fn git_renamed_files(dir: &Path, rev: &str) -> Result<HashSet<PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--name-status", "--find-renames", "--relative", "-z", rev])
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("git diff against {:?} failed: {}", rev, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    // With -z, each entry is a status followed by its paths; renames and copies list old then new.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.split('\0').filter(|field| !field.is_empty());
+    let mut renamed = HashSet::new();
+    while let Some(status) = fields.next() {
+        if status.starts_with('R') || status.starts_with('C') {
+            let (_, new) = (fields.next(), fields.next());
+            if let Some(path) = new.and_then(|new| dir.join(new).canonicalize().ok()) {
+                renamed.insert(path);
+            }
+        } else {
+            fields.next();
+        }
+    }
+    Ok(renamed)
+}
@@ -0,0 +1,110 @@
+// src/commands/undo.rs
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+
+use crate::cli::{HistoryArgs, UndoArgs};
+use crate::journal::{content_hash, state_dir, Entry, Journal};
+use super::changes::ChangeWriter;
+
+/// Handles the 'undo' subcommand logic.
+pub fn undo(args: &UndoArgs) -> Result<()> {
+    let mut journal = match &args.run_id {
+        Some(id) => Journal::load(id)?,
+        None => {
+            // Only runs made here count, so `undo` never reverts another project's run.
+            let cwd = std::env::current_dir()?;
+            Journal::all()?.into_iter().find(|run| !run.undone && run.is_within(&cwd)).with_context(|| {
+                format!(
+                    "Nothing to undo in {}. Run `filedress history --all` to list runs elsewhere, then `filedress undo <RUN>`.",
+                    cwd.display()
+                )
+            })?
+        }
+    };
+    if journal.undone {
+        bail!("Run {} was already undone", journal.id);
+    }
+    println!("Undoing run {} ('{}' in {:?})", journal.id, journal.command, journal.directory);
+
+    // Edits made after the run would be lost, so they need --force.
+    let changed: Vec<&Entry> = journal.entries.iter().filter(|entry| changed_since_run(entry)).collect();
+    if !changed.is_empty() {
+        for entry in &changed {
+            println!("[CHANGED] {}", entry.path().display());
+        }
+        if !args.force {
+            bail!("{} file(s) were changed after run {}; use --force to restore them anyway", changed.len(), journal.id);
+        }
+    }
+
+    let mut writer = ChangeWriter::new(&journal.directory, args.dry_run);
+    for entry in journal.entries.iter().rev() {
+        match entry {
+            Entry::Modified { path, original, .. } => {
+                let current = fs::read_to_string(path).unwrap_or_default();
+                if current != *original {
+                    writer.write(path, &current, original)?;
+                    println!("[RESTORED] {}", path.display());
+                }
+            }
+            Entry::CreatedFile { path, .. } => {
+                if path.exists() {
+                    writer.remove_file(path)?;
+                    if !args.dry_run {
+                        println!("[DELETED FILE] {}", path.display());
+                    }
+                }
+            }
+            Entry::CreatedDir { path } => {
+                if writer.remove_empty_dir(path)? {
+                    if !args.dry_run {
+                        println!("[DELETED DIR]  {}", path.display());
+                    }
+                } else if path.exists() {
+                    println!("[KEPT] Directory is not empty: {}", path.display());
+                }
+            }
+        }
+    }
+    writer.finish()?;
+
+    if !args.dry_run {
+        journal.undone = true;
+        journal.write()?;
+        println!("\n'undo' command finished: run {} reverted.", journal.id);
+    }
+    Ok(())
+}
+
+/// Whether the path no longer holds what the run left there.
+fn changed_since_run(entry: &Entry) -> bool {
+    let current = fs::read_to_string(entry.path());
+    let differs = |written: &str| current.as_ref().map_or(true, |content| content_hash(content) != written);
+    match entry {
+        // A run killed between journaling a write and making it left the original in place.
+        Entry::Modified { original, written, .. } => differs(written) && current.as_ref().ok() != Some(original),
+        Entry::CreatedFile { path, written } => path.exists() && differs(written),
+        Entry::CreatedDir { .. } => false,
+    }
+}
+
+/// Handles the 'history' subcommand logic.
+pub fn history(args: &HistoryArgs) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let runs: Vec<Journal> = Journal::all()?.into_iter().filter(|run| args.all || run.is_within(&cwd)).collect();
+    if runs.is_empty() {
+        if args.all {
+            println!("No runs recorded in {}", state_dir()?.display());
+        } else {
+            println!("No runs recorded in {}. Use --all to list runs in other directories.", cwd.display());
+        }
+        return Ok(());
+    }
+    println!("{:<20} {:<10} {:>8}  DIRECTORY", "RUN", "COMMAND", "CHANGES");
+    for run in runs.iter().take(args.limit) {
+        let undone = if run.undone { "  (undone)" } else { "" };
+        println!("{:<20} {:<10} {:>8}  {}{}", run.id, run.command, run.entries.len(), run.directory.display(), undone);
+    }
+    Ok(())
+}
@@ -1,75 +1,227 @@
 // FILE: src/commands/utils.rs
 
 use anyhow::{Context, Result};
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use walkdir::{DirEntry, WalkDir};
+use std::sync::Mutex;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{DirEntry, WalkBuilder};
 
-use crate::cli::{Args, ProjectType};
-use crate::file_utils::get_all_supported_extensions;
+use crate::cli::Args;
+use crate::config::Config;
+use crate::header::HeaderFormat;
+use crate::languages::LanguageRegistry;
+
+/// Builds the language registry for a run, merging in languages from the config files.
+pub fn load_registry(dir: &Path) -> Result<LanguageRegistry> {
+    let config = Config::load(dir)?;
+    LanguageRegistry::with_user_languages(&config.languages)
+}
+
+/// Builds the header format for a run from the `[header]` config section.
+pub fn load_header_format(dir: &Path) -> Result<HeaderFormat> {
+    HeaderFormat::from_config(&Config::load(dir)?.header)
+}
 
 /// Determines the final list of extensions based on user arguments.
-pub fn resolve_extensions(args: &Args) -> Vec<String> {
+pub fn resolve_extensions(args: &Args, registry: &LanguageRegistry) -> Vec<String> {
     if let Some(project_type) = &args.project {
-        return match project_type {
-            ProjectType::Rust => vec!["rs".to_string()],
-            ProjectType::Python => vec!["py".to_string()],
-            ProjectType::Web => vec!["ts", "js", "jsx", "tsx", "svelte", "vue", "html", "css", "scss"]
-                .iter().map(|s| s.to_string()).collect(),
-            ProjectType::Java => vec!["java".to_string(), "xml".to_string()],
-            ProjectType::Flutter => vec!["dart".to_string()],
-        };
+        registry.preset_extensions(project_type)
     } else if let Some(custom_exts) = &args.exts {
-        return custom_exts.clone();
+        custom_exts.clone()
     } else {
-        return get_all_supported_extensions();
+        registry.all_extensions()
     }
 }
 
-/// Creates a configured WalkDir iterator.
+/// The tool-specific ignore file, read alongside `.gitignore` and `.ignore`.
+pub const IGNORE_FILE: &str = ".filedressignore";
+
+/// Decides which files a command operates on, based on `--project` and `--exts`.
+pub struct FileSelector<'a> {
+    registry: &'a LanguageRegistry,
+    /// `None` selects every file the registry recognises.
+    exts: Option<Vec<String>>,
+}
+
+impl<'a> FileSelector<'a> {
+    pub fn new(args: &Args, registry: &'a LanguageRegistry) -> Self {
+        let exts = (args.project.is_some() || args.exts.is_some()).then(|| resolve_extensions(args, registry));
+        FileSelector { registry, exts }
+    }
+
+    /// A file is selected if its extension or its exact name (e.g. `Makefile`) is listed,
+    /// or if the language that claims it by name or shebang (e.g. `.bashrc` for `sh`) has a
+    /// listed extension.
+    pub fn matches(&self, path: &Path) -> bool {
+        let Some(exts) = &self.exts else {
+            return self.registry.detect_file(path).is_some();
+        };
+        let listed = |s: Option<&str>| s.is_some_and(|s| exts.iter().any(|e| e == s));
+        listed(path.extension().and_then(|s| s.to_str()))
+            || listed(path.file_name().and_then(|s| s.to_str()))
+            || self
+                .registry
+                .detect_by_name_or_content(path)
+                .is_some_and(|lang| lang.extensions.iter().any(|e| exts.contains(e)))
+    }
+}
+
+/// Compiles a list of glob patterns, or returns `None` if there are none.
+fn build_glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Creates a configured directory walker for the files selected by `args` and `selector`.
+///
+/// Unless `--no-ignore` is set, it honours `.gitignore`, `.git/info/exclude`, the global
+/// git excludes, `.ignore` and `.filedressignore`. Hidden files are still visited.
+/// `--include`/`--exclude` globs are matched against the path relative to the directory.
 pub fn create_file_walker<'a>(
-    dir: &'a Path,
-    exts: &'a [String],
-    depth: Option<usize>,
-) -> impl Iterator<Item = DirEntry> + 'a {
-    let mut walker_builder = WalkDir::new(dir);
-    if let Some(d) = depth {
-        walker_builder = walker_builder.max_depth(d);
-    }
-
-    walker_builder.into_iter().filter_map(|e| e.ok()).filter(move |e| {
-        e.file_type().is_file()
-            && e.path()
-                .extension()
-                .and_then(|s| s.to_str())
-                .map_or(false, |s| exts.contains(&s.to_string()))
-    })
+    args: &'a Args,
+    selector: &'a FileSelector,
+) -> Result<impl Iterator<Item = DirEntry> + 'a> {
+    walk_targets(args, selector, Vec::new())
+}
+
+/// Like `create_file_walker`, but only visits `targets` (files or directories under
+/// `args.directory`) and the directories leading to them. Empty `targets` visits everything.
+fn walk_targets<'a>(
+    args: &'a Args,
+    selector: &'a FileSelector,
+    targets: Vec<PathBuf>,
+) -> Result<impl Iterator<Item = DirEntry> + 'a> {
+    let dir = args.directory.as_path();
+    let includes = build_glob_set(&args.include)?;
+    let excludes = build_glob_set(&args.exclude)?;
+
+    let mut walker_builder = WalkBuilder::new(dir);
+    walker_builder
+        .max_depth(args.depth)
+        .standard_filters(!args.no_ignore)
+        .hidden(false)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |e| {
+            e.file_name() != ".git"
+                && (targets.is_empty()
+                    || targets.iter().any(|target| target.starts_with(e.path()) || e.path().starts_with(target)))
+        });
+    if !args.no_ignore {
+        walker_builder.add_custom_ignore_filename(IGNORE_FILE);
+    }
+
+    Ok(walker_builder.build().filter_map(|e| e.ok()).filter(move |e| {
+        let relative = e.path().strip_prefix(dir).unwrap_or(e.path());
+        e.file_type().is_some_and(|t| t.is_file())
+            && selector.matches(e.path())
+            && includes.as_ref().is_none_or(|set| set.is_match(relative))
+            && !excludes.as_ref().is_some_and(|set| set.is_match(relative))
+    }))
+}
+
+/// Computes header paths for many files under one target directory.
+///
+/// The target directory is canonicalized once, and each parent directory's canonical
+/// form is cached, so a run costs one `canonicalize` per directory instead of per file.
+pub struct DisplayPaths {
+    strip_root: PathBuf,
+    dirs: Mutex<HashMap<PathBuf, PathBuf>>,
 }
 
-/// Generates the path to be displayed in the header based on the target directory and --up levels.
-pub fn generate_display_path(file_path: &Path, target_dir: &Path, up_levels: u32) -> Result<PathBuf> {
-    let absolute_target_dir = target_dir.canonicalize()
-        .with_context(|| format!("Failed to canonicalize target directory: {}", target_dir.display()))?;
-    let absolute_file_path = file_path.canonicalize()
-        .with_context(|| format!("Failed to canonicalize file path: {}", file_path.display()))?;
-
-    // Calculate the effective root to strip from file_path
-    let mut effective_strip_root = absolute_target_dir.clone(); // Start at target_dir
-
-    for _ in 0..up_levels {
-        if let Some(parent) = effective_strip_root.parent() {
-            effective_strip_root = parent.to_path_buf();
-        } else {
-            // Cannot go up further, probably at filesystem root
-            break;
+impl DisplayPaths {
+    pub fn new(target_dir: &Path, up_levels: u32) -> Result<Self> {
+        let absolute_target_dir = target_dir.canonicalize()
+            .with_context(|| format!("Failed to canonicalize target directory: {}", target_dir.display()))?;
+
+        // Calculate the effective root to strip from file_path
+        let mut effective_strip_root = absolute_target_dir; // Start at target_dir
+
+        for _ in 0..up_levels {
+            if let Some(parent) = effective_strip_root.parent() {
+                effective_strip_root = parent.to_path_buf();
+            } else {
+                // Cannot go up further, probably at filesystem root
+                break;
+            }
         }
+        Ok(DisplayPaths { strip_root: effective_strip_root, dirs: Mutex::new(HashMap::new()) })
+    }
+
+    /// The path to show in the header of `file_path`, relative to the (shifted) target directory.
+    pub fn display_path(&self, file_path: &Path) -> Result<PathBuf> {
+        let absolute_file_path = match (file_path.parent(), file_path.file_name()) {
+            (Some(parent), Some(name)) => self.canonical_dir(parent)?.join(name),
+            _ => file_path.canonicalize()
+                .with_context(|| format!("Failed to canonicalize file path: {}", file_path.display()))?,
+        };
+
+        // Strip the `effective_strip_root` from the `absolute_file_path`.
+        // The returned path will be relative to `effective_strip_root`.
+        absolute_file_path
+            .strip_prefix(&self.strip_root)
+            .map(|p| p.to_path_buf())
+            .with_context(|| format!("Failed to create relative path for {} from base {}", file_path.display(), self.strip_root.display()))
     }
 
-    // Strip the `effective_strip_root` from the `absolute_file_path`.
-    // The returned path will be relative to `effective_strip_root`.
-    absolute_file_path
-        .strip_prefix(&effective_strip_root)
-        .map(|p| p.to_path_buf())
-        .with_context(|| format!("Failed to create relative path for {} from base {}", file_path.display(), effective_strip_root.display()))
+    fn canonical_dir(&self, dir: &Path) -> Result<PathBuf> {
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        if let Some(cached) = self.dirs.lock().unwrap().get(dir) {
+            return Ok(cached.clone());
+        }
+        let canonical = dir.canonicalize()
+            .with_context(|| format!("Failed to canonicalize directory: {}", dir.display()))?;
+        self.dirs.lock().unwrap().insert(dir.to_path_buf(), canonical.clone());
+        Ok(canonical)
+    }
+}
+
+/// Collects the files selected by `args`, in the walker's sorted order.
+pub fn collect_files(args: &Args, selector: &FileSelector) -> Result<Vec<PathBuf>> {
+    Ok(create_file_walker(args, selector)?.map(|e| e.into_path()).collect())
+}
+
+/// The selected files among `targets` and in the directories among them. Each target costs
+/// a listing of the directories above it rather than a walk of the whole tree.
+pub fn collect_files_at(args: &Args, selector: &FileSelector, targets: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(walk_targets(args, selector, targets)?.map(|e| e.into_path()).collect())
+}
+
+/// How many files each parallel batch holds per thread. Bounds how much content is held in memory.
+const FILES_PER_THREAD: usize = 64;
+
+/// Runs `process` on every file across `jobs` threads (all CPUs if `None`), then hands
+/// the results to `apply` in the order of `files`, so output matches a serial run.
+pub fn process_in_order<T, P, A>(files: &[PathBuf], jobs: Option<usize>, process: P, mut apply: A) -> Result<()>
+where
+    T: Send,
+    P: Fn(&Path) -> Result<T> + Sync,
+    A: FnMut(&Path, T) -> Result<()>,
+{
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0))
+        .build()
+        .context("Failed to start worker threads")?;
+    let batch_size = pool.current_num_threads() * FILES_PER_THREAD;
+
+    for batch in files.chunks(batch_size) {
+        let results: Vec<Result<T>> = pool.install(|| batch.par_iter().map(|path| process(path)).collect());
+        for (path, result) in batch.iter().zip(results) {
+            apply(path, result?)?;
+        }
+    }
+    Ok(())
 }
 
 
@@ -89,7 +241,13 @@ mod tests {
             up: 0,
             depth: None,
             force: false,
+            migrate: false,
             output: None,
+            no_ignore: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            dry_run: false,
+            jobs: None,
         }
     }
 
@@ -97,7 +255,7 @@ mod tests {
     fn test_resolve_project_preset() {
         let mut args = mock_args();
         args.project = Some(ProjectType::Python);
-        let exts = resolve_extensions(&args);
+        let exts = resolve_extensions(&args, &LanguageRegistry::builtin());
         assert_eq!(exts, vec!["py".to_string()]);
     }
 
@@ -105,20 +263,55 @@ mod tests {
     fn test_resolve_custom_exts() {
         let mut args = mock_args();
         args.exts = Some(vec!["toml".to_string(), "yaml".to_string()]);
-        let exts = resolve_extensions(&args);
+        let exts = resolve_extensions(&args, &LanguageRegistry::builtin());
         assert_eq!(exts, vec!["toml".to_string(), "yaml".to_string()]);
     }
 
     #[test]
     fn test_resolve_default_to_all() {
         let args = mock_args();
-        let exts = resolve_extensions(&args);
+        let exts = resolve_extensions(&args, &LanguageRegistry::builtin());
         assert!(exts.contains(&"rs".to_string()));
         assert!(exts.contains(&"py".to_string()));
         assert!(exts.contains(&"svelte".to_string()));
         assert!(!exts.is_empty());
     }
 
+    #[test]
+    fn test_selector_matches_file_names() {
+        let registry = LanguageRegistry::builtin();
+        let all = FileSelector::new(&mock_args(), &registry);
+        assert!(all.matches(Path::new("Dockerfile")));
+        assert!(all.matches(Path::new("src/main.rs")));
+        assert!(!all.matches(Path::new("notes.txt")));
+
+        let mut args = mock_args();
+        args.exts = Some(vec!["sh".to_string(), "Makefile".to_string()]);
+        let custom = FileSelector::new(&args, &registry);
+        assert!(custom.matches(Path::new("build.sh")));
+        assert!(custom.matches(Path::new(".bashrc")));
+        assert!(custom.matches(Path::new("Makefile")));
+        assert!(!custom.matches(Path::new("Dockerfile")));
+    }
+
+    #[test]
+    fn test_process_in_order_keeps_file_order() -> Result<()> {
+        let files: Vec<PathBuf> = (0..1000).map(|i| PathBuf::from(format!("f{}", i))).collect();
+        let mut seen = Vec::new();
+        process_in_order(&files, Some(4), |path| Ok(path.to_path_buf()), |path, result| {
+            assert_eq!(path, result);
+            seen.push(result);
+            Ok(())
+        })?;
+        assert_eq!(seen, files);
+        Ok(())
+    }
+
+    /// Resolves one path with a fresh `DisplayPaths`, i.e. without anything cached.
+    fn generate_display_path(file_path: &Path, target_dir: &Path, up_levels: u32) -> Result<PathBuf> {
+        DisplayPaths::new(target_dir, up_levels)?.display_path(file_path)
+    }
+
     #[test]
     fn test_generate_display_path_simple() -> Result<()> {
         let temp_dir = tempdir()?;
@@ -173,4 +366,31 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_cached_display_paths_match_uncached_ones() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let root = temp_dir.path().join("monorepo");
+        let mut files = Vec::new();
+        for dir in ["src", "src/api", "docs"] {
+            fs::create_dir_all(root.join(dir))?;
+            for name in ["a.rs", "b.rs"] {
+                let file_path = root.join(dir).join(name);
+                fs::File::create(&file_path)?;
+                files.push(file_path);
+            }
+        }
+
+        // What canonicalizing every file on its own gives.
+        let strip_root = root.canonicalize()?.parent().context("monorepo has a parent")?.to_path_buf();
+        let uncached = |file_path: &Path| -> Result<PathBuf> { Ok(file_path.canonicalize()?.strip_prefix(&strip_root)?.to_path_buf()) };
+
+        let display_paths = DisplayPaths::new(&root, 1)?;
+        // Twice over, so the second round is served from the cache.
+        for file_path in files.iter().chain(&files) {
+            assert_eq!(display_paths.display_path(file_path)?, uncached(file_path)?);
+        }
+        assert_eq!(display_paths.display_path(&files[2])?, PathBuf::from("monorepo").join("src").join("api").join("a.rs"));
+        Ok(())
+    }
 }
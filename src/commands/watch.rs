@@ -0,0 +1,257 @@
+// src/commands/watch.rs
+
+use anyhow::{Context, Result};
+use notify::event::{CreateKind, ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode};
+use notify_debouncer_full::new_debouncer;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::cli::{Args, WatchArgs};
+use crate::header::HeaderFormat;
+use crate::languages::LanguageRegistry;
+use super::add::add_header;
+use super::changes::{ChangeWriter, Outcome};
+use super::copy::copy;
+use super::sync::{sync_file, Status};
+use super::utils::{collect_files, collect_files_at, load_header_format, load_registry, DisplayPaths, FileSelector};
+
+/// What happened to a path, as far as `watch` cares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// A file or directory was created or moved here. Its files get a header, or a fixed one.
+    Arrived(PathBuf),
+    /// A file was edited or went away. Only the bundle cares.
+    Touched(PathBuf),
+}
+
+/// Handles the 'watch' subcommand logic. Runs until interrupted.
+pub fn watch(watch_args: &WatchArgs) -> Result<()> {
+    let args = &watch_args.args;
+    let root = args
+        .directory
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve directory: {}", args.directory.display()))?;
+    let mut state = WatchState::new(args)?;
+    if state.bundle.is_some() {
+        state.rebundle()?;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(watch_args.debounce), None, tx)?;
+    debouncer.watch(&root, RecursiveMode::Recursive)?;
+    println!("Watching {:?} for changes. Press Ctrl+C to stop.", &args.directory);
+
+    for result in rx {
+        match result {
+            Ok(events) => {
+                let changes = events.iter().flat_map(|event| classify(&event.kind, &event.paths)).collect();
+                // One bad file shouldn't stop the watcher.
+                if let Err(e) = state.handle(changes) {
+                    eprintln!("Error: {:#}", e);
+                }
+            }
+            Err(errors) => errors.iter().for_each(|e| eprintln!("Watch error: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+/// Turns a debounced event into the changes `watch` acts on.
+fn classify(kind: &EventKind, paths: &[PathBuf]) -> Vec<Change> {
+    match kind {
+        EventKind::Create(CreateKind::File | CreateKind::Folder | CreateKind::Any) => {
+            paths.iter().cloned().map(Change::Arrived).collect()
+        }
+        // The debouncer pairs both halves of a rename; `paths` is then `[from, to]`.
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+            vec![Change::Touched(paths[0].clone()), Change::Arrived(paths[1].clone())]
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => paths.iter().cloned().map(Change::Arrived).collect(),
+        EventKind::Modify(_) | EventKind::Remove(_) => paths.iter().cloned().map(Change::Touched).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Everything `watch` keeps between batches of events.
+struct WatchState<'a> {
+    args: &'a Args,
+    registry: LanguageRegistry,
+    format: HeaderFormat,
+    display_paths: DisplayPaths,
+    /// The canonical watched directory, which event paths are relative to.
+    root: PathBuf,
+    writer: ChangeWriter,
+    /// A hash of the content of each file we wrote, so our own writes don't trigger more work.
+    own_writes: HashMap<PathBuf, u64>,
+    /// The canonical path of the `--output` bundle, if there is one.
+    bundle: Option<PathBuf>,
+    /// The files in the last bundle, so deleting one of them rebuilds it.
+    bundled: HashSet<PathBuf>,
+}
+
+impl<'a> WatchState<'a> {
+    fn new(args: &'a Args) -> Result<Self> {
+        let bundle = args.output.as_deref().map(canonical).transpose()?;
+        Ok(WatchState {
+            args,
+            registry: load_registry(&args.directory)?,
+            format: load_header_format(&args.directory)?,
+            display_paths: DisplayPaths::new(&args.directory, args.up)?,
+            root: canonical(&args.directory)?,
+            writer: ChangeWriter::new(&args.directory, args.dry_run).journaled("watch"),
+            own_writes: HashMap::new(),
+            bundle,
+            bundled: HashSet::new(),
+        })
+    }
+
+    /// Acts on one debounced batch of changes.
+    fn handle(&mut self, changes: Vec<Change>) -> Result<()> {
+        let changes: Vec<(PathBuf, bool)> = changes
+            .into_iter()
+            .filter_map(|change| match change {
+                Change::Arrived(path) => Some((canonical(&path).ok()?, true)),
+                Change::Touched(path) => Some((canonical(&path).ok()?, false)),
+            })
+            .filter(|(path, _)| Some(path) != self.bundle.as_ref() && !self.is_own_write(path))
+            .collect();
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        // Only the arrivals are walked: a moved directory brings all of its files along, and a
+        // file the selector rejects isn't visited at all. The walk applies the ignore files and
+        // globs the same way as the other commands.
+        let selector = FileSelector::new(self.args, &self.registry);
+        let targets: Vec<PathBuf> = changes
+            .iter()
+            .filter(|(path, arrived)| *arrived && (path.is_dir() || selector.matches(path)))
+            .filter_map(|(path, _)| Some(self.args.directory.join(path.strip_prefix(&self.root).ok()?)))
+            .collect();
+        let mut arrivals: Vec<(PathBuf, PathBuf)> = collect_files_at(self.args, &selector, targets)?
+            .into_iter()
+            .filter_map(|file| Some((canonical(&file).ok()?, file)))
+            .collect();
+        arrivals.sort();
+        arrivals.dedup();
+        for (canonical_path, file) in &arrivals {
+            if let Some(outcome) = self.dress_file(file)? {
+                if let Some(content) = outcome.new_content() {
+                    self.own_writes.insert(canonical_path.clone(), hash(content));
+                }
+                self.writer.apply(file, outcome)?;
+            }
+        }
+
+        // Each batch is its own run in the journal.
+        self.writer.finish()?;
+
+        let bundle_changed = !arrivals.is_empty()
+            || changes.iter().any(|(path, _)| self.bundled.iter().any(|file| file.starts_with(path)));
+        if self.bundle.is_some() && bundle_changed {
+            self.rebundle()?;
+        }
+        Ok(())
+    }
+
+    /// Adds a header to a file without one, or fixes one that points at an old path.
+    fn dress_file(&self, file: &Path) -> Result<Option<Outcome>> {
+        match sync_file(file, &self.registry, &self.format, &self.display_paths)? {
+            Some(Status::NoHeader) => add_header(file, self.args, &self.registry, &self.format, &self.display_paths),
+            Some(Status::Stale(outcome)) => Ok(Some(outcome)),
+            Some(Status::UpToDate) | None => Ok(None),
+        }
+    }
+
+    fn rebundle(&mut self) -> Result<()> {
+        copy(self.args)?;
+        let selector = FileSelector::new(self.args, &self.registry);
+        self.bundled = collect_files(self.args, &selector)?.iter().filter_map(|path| canonical(path).ok()).collect();
+        Ok(())
+    }
+
+    /// Whether `path` still has the content we last wrote to it.
+    fn is_own_write(&self, path: &Path) -> bool {
+        self.own_writes.get(path).is_some_and(|&written| fs::read_to_string(path).is_ok_and(|c| hash(&c) == written))
+    }
+}
+
+/// Canonicalises `path`, or its parent for a path that no longer exists.
+fn canonical(path: &Path) -> Result<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Ok(path);
+    }
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().with_context(|| format!("Invalid path: {}", path.display()))?;
+    Ok(parent.canonicalize()?.join(name))
+}
+
+fn hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_events() {
+        let paths = [PathBuf::from("a.rs"), PathBuf::from("b.rs")];
+        let [a, b] = paths.clone();
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        assert_eq!(classify(&rename, &paths), vec![Change::Touched(a.clone()), Change::Arrived(b.clone())]);
+        assert_eq!(classify(&EventKind::Create(CreateKind::File), &paths[..1]), vec![Change::Arrived(a.clone())]);
+        let edit = EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Content));
+        assert_eq!(classify(&edit, &paths[..1]), vec![Change::Touched(a)]);
+        assert!(classify(&EventKind::Access(notify::event::AccessKind::Any), &paths[1..]).is_empty());
+    }
+
+    #[test]
+    fn test_handle_dresses_new_and_moved_files_once() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().canonicalize()?;
+        fs::create_dir_all(root.join("src/moved"))?;
+        fs::write(root.join("src/new.rs"), "fn new() {}\n")?;
+        fs::write(root.join("src/moved/old.rs"), "// Path:src/old.rs\nfn old() {}\n")?;
+        fs::write(root.join("notes.txt"), "not selected\n")?;
+        fs::create_dir_all(root.join("build"))?;
+        fs::write(root.join(".gitignore"), "build/\n")?;
+        fs::write(root.join("build/gen.rs"), "fn gen() {}\n")?;
+        fs::write(root.join("src/edited.rs"), "fn edited() {}\n")?;
+        let args = Args { directory: root.clone(), output: Some(root.join("bundle.md")), ..Default::default() };
+        let mut state = WatchState::new(&args)?;
+
+        state.handle(vec![
+            Change::Arrived(root.join("src/new.rs")),
+            Change::Arrived(root.join("src/moved")),
+            Change::Arrived(root.join("notes.txt")),
+            Change::Arrived(root.join("build/gen.rs")),
+            Change::Touched(root.join("src/edited.rs")),
+        ])?;
+        assert_eq!(fs::read_to_string(root.join("src/new.rs"))?, "// Path:src/new.rs\nfn new() {}\n");
+        assert_eq!(fs::read_to_string(root.join("src/moved/old.rs"))?, "// Path:src/moved/old.rs\nfn old() {}\n");
+        assert_eq!(fs::read_to_string(root.join("notes.txt"))?, "not selected\n");
+        assert_eq!(fs::read_to_string(root.join("build/gen.rs"))?, "fn gen() {}\n");
+        // Edits don't get headers, but they do rebuild the bundle.
+        assert_eq!(fs::read_to_string(root.join("src/edited.rs"))?, "fn edited() {}\n");
+        assert!(fs::read_to_string(root.join("bundle.md"))?.contains("FILE: src/edited.rs"));
+
+        // The events caused by our own writes, and by the bundle, are ignored.
+        fs::remove_file(root.join("bundle.md"))?;
+        state.handle(vec![Change::Touched(root.join("src/new.rs")), Change::Touched(root.join("bundle.md"))])?;
+        assert!(!root.join("bundle.md").exists());
+        // A real edit after ours is not.
+        fs::write(root.join("src/new.rs"), "// Path:src/new.rs\nfn newer() {}\n")?;
+        state.handle(vec![Change::Touched(root.join("src/new.rs"))])?;
+        assert!(root.join("bundle.md").exists());
+        Ok(())
+    }
+}
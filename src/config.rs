@@ -0,0 +1,663 @@
+// src/config.rs
+
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{Args, Commands, ProjectType, StructureArgs};
+use crate::languages::{HeaderStyle, Preamble};
+
+/// The name of the per-project config file, searched for upward from the target directory.
+pub const PROJECT_CONFIG_FILE: &str = ".filedress.toml";
+
+/// Overrides the location of the global config file.
+pub const CONFIG_PATH_ENV: &str = "FILEDRESS_CONFIG";
+
+/// The contents of one config file (or of the `FILEDRESS_*` environment).
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Defaults shared by `add`, `remove`, `clean`, `copy`, `check`, `comments`, `license`, `sync` and `watch`.
+    pub defaults: ArgsDefaults,
+    pub add: ArgsDefaults,
+    pub remove: ArgsDefaults,
+    pub clean: ArgsDefaults,
+    pub copy: ArgsDefaults,
+    pub check: ArgsDefaults,
+    pub comments: ArgsDefaults,
+    pub sync: ArgsDefaults,
+    pub watch: ArgsDefaults,
+    pub structure: StructureDefaults,
+    pub header: HeaderConfig,
+    pub license: LicenseConfig,
+    /// User-defined languages, keyed by name. A name that matches a built-in language overrides it.
+    pub languages: BTreeMap<String, LanguageConfig>,
+}
+
+/// Default values for the fields of `cli::Args`.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArgsDefaults {
+    pub project: Option<ProjectType>,
+    pub exts: Option<Vec<String>>,
+    pub up: Option<u32>,
+    pub depth: Option<usize>,
+    pub force: Option<bool>,
+    pub output: Option<PathBuf>,
+    pub no_ignore: Option<bool>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub jobs: Option<usize>,
+    /// Only used by `clean`.
+    pub keep_docs: Option<bool>,
+    /// Used by `clean`, and by `comments` to label directives.
+    pub keep_patterns: Option<Vec<String>>,
+    /// Only used by `clean`.
+    pub strip_docstrings: Option<bool>,
+    /// Only used by `clean`.
+    pub collapse_blank_lines: Option<usize>,
+}
+
+/// The `[header]` section: the header template and the older templates it replaces.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeaderConfig {
+    /// The text inside the comment, e.g. `"File: {path}"`. A newline starts another comment line.
+    pub template: Option<String>,
+    /// Templates used by earlier headers, which `add --migrate` rewrites to `template`.
+    pub legacy_templates: Option<Vec<String>>,
+    /// Whether `add` leaves a blank line between the header and the code.
+    pub blank_line: Option<bool>,
+}
+
+/// The `[license]` section: the license block written by `filedress license`,
+/// plus the usual `Args` defaults for that command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LicenseConfig {
+    /// The SPDX license identifier, e.g. `"MIT"`.
+    pub spdx: Option<String>,
+    /// The copyright holder, e.g. `"Acme Inc."`.
+    pub holder: Option<String>,
+    /// The copyright line, e.g. `"Copyright (c) {years} {holder}"`.
+    pub template: Option<String>,
+    pub args: ArgsDefaults,
+}
+
+impl<'de> Deserialize<'de> for LicenseConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        // The license keys share the table with the `Args` defaults. Take them out
+        // first so that `ArgsDefaults` still rejects any key nobody knows.
+        let mut table = toml::Table::deserialize(deserializer)?;
+        let mut take = |key: &str| -> std::result::Result<Option<String>, D::Error> {
+            table.remove(key).map(|value| String::deserialize(value).map_err(D::Error::custom)).transpose()
+        };
+        let spdx = take("spdx")?;
+        let holder = take("holder")?;
+        let template = take("template")?;
+        let args = ArgsDefaults::deserialize(toml::Value::Table(table)).map_err(D::Error::custom)?;
+        Ok(LicenseConfig { spdx, holder, template, args })
+    }
+}
+
+/// Default values for the fields of `cli::StructureArgs`.
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct StructureDefaults {
+    pub file: Option<PathBuf>,
+    pub directory: Option<PathBuf>,
+    pub indent: Option<u32>,
+}
+
+/// A `[languages.<name>]` section.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LanguageConfig {
+    pub extensions: Option<Vec<String>>,
+    pub filenames: Option<Vec<String>>,
+    /// Globs matched against the file name, e.g. `["*.tfvars.json", ".env.*"]`.
+    pub filename_patterns: Option<Vec<String>>,
+    /// Shebang interpreters, e.g. `["elixir"]` for `#!/usr/bin/env elixir`.
+    pub interpreters: Option<Vec<String>>,
+    /// Extra names accepted in vim `ft=` and emacs `mode:` modelines.
+    pub aliases: Option<Vec<String>>,
+    pub line_comment: Option<String>,
+    /// The block comment start and end tokens, e.g. `["/*", "*/"]`.
+    pub block_comment: Option<[String; 2]>,
+    pub nested_comments: Option<bool>,
+    /// String delimiters, e.g. `["\"\"\"", "\"", "'"]`.
+    pub strings: Option<Vec<String>>,
+    /// String delimiters without escapes, e.g. `["`"]` for Go raw strings. Each must also be in `strings`.
+    pub raw_strings: Option<Vec<String>>,
+    /// Unquoted tokens taken as-is, as opener and closer pairs, e.g. `[["url(", ")"]]`.
+    pub raw_tokens: Option<Vec<[String; 2]>>,
+    pub doc_comments: Option<Vec<String>>,
+    /// Whether `clean` keeps this language's doc comments, overriding `--keep-docs`.
+    pub keep_docs: Option<bool>,
+    /// Regexes for directive comments that `clean` keeps. Replaces the built-in list.
+    pub directives: Option<Vec<String>>,
+    pub header_style: Option<HeaderStyle>,
+    /// Constructs that must stay above the header, e.g. `["encoding-line"]`.
+    pub preamble: Option<Vec<Preamble>>,
+    /// Whether a blank line always follows the header, whatever `[header] blank_line` says.
+    pub header_blank_line: Option<bool>,
+}
+
+/// Where a resolved setting came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Default,
+    Global(PathBuf),
+    Project(PathBuf),
+    Env,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "built-in default"),
+            Source::Global(path) => write!(f, "global config {}", path.display()),
+            Source::Project(path) => write!(f, "project config {}", path.display()),
+            Source::Env => write!(f, "environment"),
+        }
+    }
+}
+
+/// The origin of one resolved field: the layer and the section (or variable) within it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    pub source: Source,
+    pub section: String,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.source, self.section)
+    }
+}
+
+/// The config layers that apply to a directory, lowest priority first:
+/// global config, project `.filedress.toml`, then `FILEDRESS_*` environment variables.
+#[derive(Debug, Default)]
+pub struct ConfigLayers {
+    layers: Vec<(Source, Config)>,
+}
+
+impl ConfigLayers {
+    /// Loads every layer that applies to `dir`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut layers = Vec::new();
+        if let Some(global) = global_config_path().filter(|p| p.is_file()) {
+            layers.push((Source::Global(global.clone()), Config::from_file(&global)?));
+        }
+        if let Some(project) = find_project_config(dir) {
+            layers.push((Source::Project(project.clone()), Config::from_file(&project)?));
+        }
+        if let Some(env) = env_config(std::env::vars())? {
+            layers.push((Source::Env, env));
+        }
+        Ok(ConfigLayers { layers })
+    }
+
+    /// The config files that were found, in priority order.
+    pub fn sources(&self) -> impl Iterator<Item = &Source> {
+        self.layers.iter().map(|(source, _)| source)
+    }
+
+    /// Resolves the `Args` defaults for a subcommand. Within a layer, the
+    /// subcommand's own section beats `[defaults]`.
+    pub fn resolve_args(&self, command: &str) -> (ArgsDefaults, BTreeMap<&'static str, Origin>) {
+        let mut resolved = ArgsDefaults::default();
+        let mut origins = BTreeMap::new();
+        for (source, config) in &self.layers {
+            resolved.overlay(&config.defaults, &mut origins, source, "defaults");
+            if let Some(section) = config.section(command) {
+                resolved.overlay(section, &mut origins, source, command);
+            }
+        }
+        (resolved, origins)
+    }
+
+    /// Resolves the `StructureArgs` defaults.
+    pub fn resolve_structure(&self) -> (StructureDefaults, BTreeMap<&'static str, Origin>) {
+        let mut resolved = StructureDefaults::default();
+        let mut origins = BTreeMap::new();
+        for (source, config) in &self.layers {
+            resolved.overlay(&config.structure, &mut origins, source);
+        }
+        (resolved, origins)
+    }
+
+    /// Resolves the `[header]` section.
+    pub fn resolve_header(&self) -> (HeaderConfig, BTreeMap<&'static str, Origin>) {
+        let mut resolved = HeaderConfig::default();
+        let mut origins = BTreeMap::new();
+        for (source, config) in &self.layers {
+            overlay_field(&mut resolved.template, &config.header.template, "template", &mut origins, source, "header");
+            overlay_field(
+                &mut resolved.legacy_templates,
+                &config.header.legacy_templates,
+                "legacy_templates",
+                &mut origins,
+                source,
+                "header",
+            );
+            overlay_field(&mut resolved.blank_line, &config.header.blank_line, "blank_line", &mut origins, source, "header");
+        }
+        (resolved, origins)
+    }
+
+    /// Resolves the `[license]` section.
+    pub fn resolve_license(&self) -> (LicenseConfig, BTreeMap<&'static str, Origin>) {
+        let mut resolved = LicenseConfig::default();
+        let mut origins = BTreeMap::new();
+        for (source, config) in &self.layers {
+            overlay_field(&mut resolved.spdx, &config.license.spdx, "spdx", &mut origins, source, "license");
+            overlay_field(&mut resolved.holder, &config.license.holder, "holder", &mut origins, source, "license");
+            overlay_field(&mut resolved.template, &config.license.template, "template", &mut origins, source, "license");
+        }
+        (resolved, origins)
+    }
+
+    /// All user-defined languages, with later layers replacing earlier definitions of the same name.
+    pub fn languages(&self) -> BTreeMap<String, (LanguageConfig, Source)> {
+        let mut languages = BTreeMap::new();
+        for (source, config) in &self.layers {
+            for (name, language) in &config.languages {
+                languages.insert(name.clone(), (language.clone(), source.clone()));
+            }
+        }
+        languages
+    }
+}
+
+impl Config {
+    /// Loads every layer that applies to `dir` and merges their language, header and license settings.
+    pub fn load(dir: &Path) -> Result<Config> {
+        let layers = ConfigLayers::load(dir)?;
+        let languages = layers.languages().into_iter().map(|(name, (lang, _))| (name, lang)).collect();
+        let (header, _) = layers.resolve_header();
+        let (license, _) = layers.resolve_license();
+        Ok(Config { languages, header, license, ..Default::default() })
+    }
+
+    /// Parses a single config file.
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+
+    fn section(&self, command: &str) -> Option<&ArgsDefaults> {
+        match command {
+            "add" => Some(&self.add),
+            "remove" => Some(&self.remove),
+            "clean" => Some(&self.clean),
+            "copy" => Some(&self.copy),
+            "check" => Some(&self.check),
+            "comments" => Some(&self.comments),
+            "license" => Some(&self.license.args),
+            "sync" => Some(&self.sync),
+            "watch" => Some(&self.watch),
+            _ => None,
+        }
+    }
+}
+
+/// Copies a set field from `value` into `slot` and records where it came from.
+fn overlay_field<T: Clone>(
+    slot: &mut Option<T>,
+    value: &Option<T>,
+    field: &'static str,
+    origins: &mut BTreeMap<&'static str, Origin>,
+    source: &Source,
+    section: &str,
+) {
+    if let Some(v) = value {
+        *slot = Some(v.clone());
+        let section = if *source == Source::Env {
+            env_var_name(field)
+        } else {
+            section.to_string()
+        };
+        origins.insert(field, Origin { source: source.clone(), section });
+    }
+}
+
+impl ArgsDefaults {
+    fn overlay(&mut self, other: &ArgsDefaults, origins: &mut BTreeMap<&'static str, Origin>, source: &Source, section: &str) {
+        // `--project` and `--exts` are alternatives, so a layer that sets one clears the other.
+        if other.project.is_some() || other.exts.is_some() {
+            self.project = None;
+            self.exts = None;
+            origins.remove("project");
+            origins.remove("exts");
+        }
+        overlay_field(&mut self.project, &other.project, "project", origins, source, section);
+        overlay_field(&mut self.exts, &other.exts, "exts", origins, source, section);
+        overlay_field(&mut self.up, &other.up, "up", origins, source, section);
+        overlay_field(&mut self.depth, &other.depth, "depth", origins, source, section);
+        overlay_field(&mut self.force, &other.force, "force", origins, source, section);
+        overlay_field(&mut self.output, &other.output, "output", origins, source, section);
+        overlay_field(&mut self.no_ignore, &other.no_ignore, "no_ignore", origins, source, section);
+        overlay_field(&mut self.include, &other.include, "include", origins, source, section);
+        overlay_field(&mut self.exclude, &other.exclude, "exclude", origins, source, section);
+        overlay_field(&mut self.jobs, &other.jobs, "jobs", origins, source, section);
+        overlay_field(&mut self.keep_docs, &other.keep_docs, "keep_docs", origins, source, section);
+        overlay_field(&mut self.keep_patterns, &other.keep_patterns, "keep_patterns", origins, source, section);
+        overlay_field(&mut self.strip_docstrings, &other.strip_docstrings, "strip_docstrings", origins, source, section);
+        overlay_field(&mut self.collapse_blank_lines, &other.collapse_blank_lines, "collapse_blank_lines", origins, source, section);
+    }
+
+    /// Fills every field of `args` that was not given on the command line.
+    pub fn apply(&self, args: &mut Args, matches: &ArgMatches) {
+        if !is_explicit(matches, "project") && !is_explicit(matches, "exts") {
+            if let Some(project) = &self.project {
+                args.project = Some(project.clone());
+            }
+            if let Some(exts) = &self.exts {
+                args.exts = Some(exts.clone());
+            }
+        }
+        if let Some(up) = self.up.filter(|_| !is_explicit(matches, "up")) {
+            args.up = up;
+        }
+        if let Some(depth) = self.depth.filter(|_| !is_explicit(matches, "depth")) {
+            args.depth = Some(depth);
+        }
+        if let Some(force) = self.force.filter(|_| !is_explicit(matches, "force")) {
+            args.force = force;
+        }
+        if let Some(output) = self.output.as_ref().filter(|_| !is_explicit(matches, "output")) {
+            args.output = Some(output.clone());
+        }
+        if let Some(no_ignore) = self.no_ignore.filter(|_| !is_explicit(matches, "no_ignore")) {
+            args.no_ignore = no_ignore;
+        }
+        if let Some(include) = self.include.as_ref().filter(|_| !is_explicit(matches, "include")) {
+            args.include = include.clone();
+        }
+        if let Some(exclude) = self.exclude.as_ref().filter(|_| !is_explicit(matches, "exclude")) {
+            args.exclude = exclude.clone();
+        }
+        if let Some(jobs) = self.jobs.filter(|_| !is_explicit(matches, "jobs")) {
+            args.jobs = Some(jobs);
+        }
+    }
+}
+
+impl StructureDefaults {
+    fn overlay(&mut self, other: &StructureDefaults, origins: &mut BTreeMap<&'static str, Origin>, source: &Source) {
+        overlay_field(&mut self.file, &other.file, "file", origins, source, "structure");
+        overlay_field(&mut self.directory, &other.directory, "directory", origins, source, "structure");
+        overlay_field(&mut self.indent, &other.indent, "indent", origins, source, "structure");
+    }
+
+    /// Fills every field of `args` that was not given on the command line.
+    pub fn apply(&self, args: &mut StructureArgs, matches: &ArgMatches) {
+        if let Some(file) = self.file.as_ref().filter(|_| !is_explicit(matches, "file")) {
+            args.file = Some(file.clone());
+        }
+        if let Some(directory) = self.directory.as_ref().filter(|_| !is_explicit(matches, "directory")) {
+            args.directory = Some(directory.clone());
+        }
+        if let Some(indent) = self.indent.filter(|_| !is_explicit(matches, "indent")) {
+            args.indent = indent;
+        }
+    }
+}
+
+fn is_explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches!(matches.value_source(id), Some(ValueSource::CommandLine))
+}
+
+fn env_var_name(field: &str) -> String {
+    format!("FILEDRESS_{}", field.to_uppercase())
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().to_string()).collect()
+}
+
+/// Builds a config layer from `FILEDRESS_*` variables. Returns `None` if none are set.
+fn env_config(vars: impl Iterator<Item = (String, String)>) -> Result<Option<Config>> {
+    let mut config = Config::default();
+    let mut found = false;
+
+    for (key, value) in vars {
+        let Some(field) = key.strip_prefix("FILEDRESS_") else { continue };
+        let invalid = || format!("Invalid value for {}: '{}'", key, value);
+        match field {
+            "PROJECT" => {
+                let project = ProjectType::from_str(&value, true).map_err(anyhow::Error::msg).with_context(invalid)?;
+                config.defaults.project = Some(project);
+            }
+            "EXTS" => config.defaults.exts = Some(split_list(&value)),
+            "UP" => config.defaults.up = Some(value.parse().with_context(invalid)?),
+            "DEPTH" => config.defaults.depth = Some(value.parse().with_context(invalid)?),
+            "FORCE" => config.defaults.force = Some(value.parse().with_context(invalid)?),
+            "OUTPUT" => config.defaults.output = Some(PathBuf::from(&value)),
+            "NO_IGNORE" => config.defaults.no_ignore = Some(value.parse().with_context(invalid)?),
+            "INCLUDE" => config.defaults.include = Some(split_list(&value)),
+            "EXCLUDE" => config.defaults.exclude = Some(split_list(&value)),
+            "JOBS" => config.defaults.jobs = Some(value.parse().with_context(invalid)?),
+            "KEEP_DOCS" => config.defaults.keep_docs = Some(value.parse().with_context(invalid)?),
+            "STRIP_DOCSTRINGS" => config.defaults.strip_docstrings = Some(value.parse().with_context(invalid)?),
+            "COLLAPSE_BLANK_LINES" => config.defaults.collapse_blank_lines = Some(value.parse().with_context(invalid)?),
+            "INDENT" => config.structure.indent = Some(value.parse().with_context(invalid)?),
+            _ => continue,
+        }
+        found = true;
+    }
+    // Like `--project` and `--exts` on the command line, these are alternatives.
+    if config.defaults.project.is_some() && config.defaults.exts.is_some() {
+        anyhow::bail!("FILEDRESS_PROJECT and FILEDRESS_EXTS can't be set together; unset one of them");
+    }
+    Ok(found.then_some(config))
+}
+
+/// Applies every config layer to the parsed command. Values given on the
+/// command line always win.
+pub fn apply_config(command: &mut Commands, matches: &ArgMatches) -> Result<()> {
+    let Some((name, sub_matches)) = matches.subcommand() else { return Ok(()) };
+    match command {
+        Commands::Add(args) | Commands::Remove(args) | Commands::Copy(args) => {
+            let (defaults, _) = ConfigLayers::load(&args.directory)?.resolve_args(name);
+            defaults.apply(args, sub_matches);
+        }
+        Commands::Clean(clean) => {
+            let (defaults, _) = ConfigLayers::load(&clean.args.directory)?.resolve_args(name);
+            defaults.apply(&mut clean.args, sub_matches);
+            if let Some(keep_docs) = defaults.keep_docs.filter(|_| !is_explicit(sub_matches, "keep_docs")) {
+                clean.keep_docs = keep_docs;
+            }
+            if let Some(patterns) = defaults.keep_patterns.filter(|_| !is_explicit(sub_matches, "keep_patterns")) {
+                clean.keep_patterns = patterns;
+            }
+            // Either flag on the command line decides; `--keep-docstrings` exists to override this.
+            let docstrings_explicit = is_explicit(sub_matches, "strip_docstrings") || is_explicit(sub_matches, "keep_docstrings");
+            if let Some(strip) = defaults.strip_docstrings.filter(|_| !docstrings_explicit) {
+                clean.strip_docstrings = strip;
+            }
+            if let Some(max) = defaults.collapse_blank_lines.filter(|_| !is_explicit(sub_matches, "collapse_blank_lines")) {
+                clean.collapse_blank_lines = Some(max);
+            }
+        }
+        Commands::Check(check) => {
+            let (defaults, _) = ConfigLayers::load(&check.args.directory)?.resolve_args(name);
+            defaults.apply(&mut check.args, sub_matches);
+        }
+        Commands::Comments(comments) => {
+            let layers = ConfigLayers::load(&comments.args.directory)?;
+            let (defaults, _) = layers.resolve_args(name);
+            defaults.apply(&mut comments.args, sub_matches);
+            // Directives are labelled with the patterns `clean` keeps.
+            let (clean_defaults, _) = layers.resolve_args("clean");
+            if let Some(patterns) = clean_defaults.keep_patterns.filter(|_| !is_explicit(sub_matches, "keep_patterns")) {
+                comments.keep_patterns = patterns;
+            }
+        }
+        Commands::License(license) => {
+            let (defaults, _) = ConfigLayers::load(&license.args.directory)?.resolve_args(name);
+            defaults.apply(&mut license.args, sub_matches);
+        }
+        Commands::Sync(sync) => {
+            let (defaults, _) = ConfigLayers::load(&sync.args.directory)?.resolve_args(name);
+            defaults.apply(&mut sync.args, sub_matches);
+        }
+        Commands::Watch(watch) => {
+            let (defaults, _) = ConfigLayers::load(&watch.args.directory)?.resolve_args(name);
+            defaults.apply(&mut watch.args, sub_matches);
+        }
+        Commands::Structure(args) => {
+            let (defaults, _) = ConfigLayers::load(args.directory.as_deref().unwrap_or(Path::new(".")))?.resolve_structure();
+            defaults.apply(args, sub_matches);
+        }
+        Commands::Config(_) | Commands::Undo(_) | Commands::History(_) => {}
+    }
+    Ok(())
+}
+
+/// Gets the path to the global config file (~/.config/filedress/config.toml on Linux).
+pub fn global_config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_PATH_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    dirs::config_dir().map(|p| p.join("filedress").join("config.toml"))
+}
+
+/// Searches `dir` and its ancestors for a `.filedress.toml`.
+pub fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    let start = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    start
+        .ancestors()
+        .map(|ancestor| ancestor.join(PROJECT_CONFIG_FILE))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn layer(source: Source, toml: &str) -> (Source, Config) {
+        (source, toml::from_str(toml).unwrap())
+    }
+
+    #[test]
+    fn test_parse_language_section() -> Result<()> {
+        let config: Config = toml::from_str(
+            r##"
+            [languages.terraform]
+            extensions = ["tf", "tfvars"]
+            line_comment = "#"
+            block_comment = ["/*", "*/"]
+            strings = ["\""]
+            "##,
+        )?;
+        let tf = &config.languages["terraform"];
+        assert_eq!(tf.extensions.as_deref(), Some(&["tf".to_string(), "tfvars".to_string()][..]));
+        assert_eq!(tf.line_comment.as_deref(), Some("#"));
+        assert_eq!(tf.block_comment, Some(["/*".to_string(), "*/".to_string()]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let parsed: Result<Config, _> = toml::from_str("[languages.zig]\nline = \"//\"\n");
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_license_section_holds_args_defaults() -> Result<()> {
+        let layers = ConfigLayers {
+            layers: vec![layer(
+                Source::Project(PathBuf::from("/p.toml")),
+                "[defaults]\nup = 1\n[license]\nspdx = \"MIT\"\nup = 2\ninclude = [\"src/**\"]\n",
+            )],
+        };
+        let (license_args, origins) = layers.resolve_args("license");
+        assert_eq!(license_args.up, Some(2));
+        assert_eq!(license_args.include, Some(vec!["src/**".to_string()]));
+        assert_eq!(origins["up"].section, "license");
+        let (license, _) = layers.resolve_license();
+        assert_eq!(license.spdx.as_deref(), Some("MIT"));
+
+        assert!(toml::from_str::<Config>("[license]\nspxd = \"MIT\"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_project_config_searches_upward() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let nested = temp_dir.path().join("a").join("b");
+        fs::create_dir_all(&nested)?;
+        fs::write(temp_dir.path().join(PROJECT_CONFIG_FILE), "")?;
+
+        let found = find_project_config(&nested).expect("config should be found");
+        assert_eq!(found, temp_dir.path().canonicalize()?.join(PROJECT_CONFIG_FILE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_layers_resolve_in_priority_order() {
+        let global = PathBuf::from("/global.toml");
+        let project = PathBuf::from("/repo/.filedress.toml");
+        let layers = ConfigLayers {
+            layers: vec![
+                layer(Source::Global(global.clone()), "[defaults]\nup = 1\ndepth = 2\n[add]\nforce = true\n"),
+                layer(Source::Project(project.clone()), "[defaults]\nup = 3\n[add]\ndepth = 5\n"),
+                layer(Source::Env, "[defaults]\ndepth = 7\n"),
+            ],
+        };
+
+        let (add, origins) = layers.resolve_args("add");
+        assert_eq!(add.up, Some(3));
+        assert_eq!(add.depth, Some(7));
+        assert_eq!(add.force, Some(true));
+        assert_eq!(origins["up"].source, Source::Project(project));
+        assert_eq!(origins["depth"].section, "FILEDRESS_DEPTH");
+        assert_eq!(origins["force"], Origin { source: Source::Global(global), section: "add".to_string() });
+
+        let (remove, _) = layers.resolve_args("remove");
+        assert_eq!(remove.force, None);
+        assert_eq!(remove.depth, Some(7));
+    }
+
+    #[test]
+    fn test_later_layer_exts_replaces_project_preset() {
+        let layers = ConfigLayers {
+            layers: vec![
+                layer(Source::Global(PathBuf::from("/g.toml")), "[defaults]\nproject = \"rust\"\n"),
+                layer(Source::Project(PathBuf::from("/p.toml")), "[copy]\nexts = [\"ts\"]\n"),
+            ],
+        };
+        let (copy, origins) = layers.resolve_args("copy");
+        assert_eq!(copy.project, None);
+        assert_eq!(copy.exts, Some(vec!["ts".to_string()]));
+        assert!(!origins.contains_key("project"));
+    }
+
+    #[test]
+    fn test_env_config() -> Result<()> {
+        let vars = |pairs: &[(&str, &str)]| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>().into_iter();
+        let config = env_config(vars(&[("FILEDRESS_UP", "2"), ("FILEDRESS_EXTS", "rs, toml"), ("HOME", "/x")]))?
+            .expect("env layer should exist");
+        assert_eq!(config.defaults.up, Some(2));
+        assert_eq!(config.defaults.exts, Some(vec!["rs".to_string(), "toml".to_string()]));
+        let config = env_config(vars(&[("FILEDRESS_PROJECT", "Python")]))?.expect("env layer should exist");
+        assert_eq!(config.defaults.project, Some(ProjectType::Python));
+        assert!(env_config(vars(&[("FILEDRESS_EXTS", "rs"), ("FILEDRESS_PROJECT", "Python")])).is_err());
+
+        assert!(env_config(std::iter::once(("FILEDRESS_UP".to_string(), "x".to_string()))).is_err());
+        assert!(env_config(std::iter::empty())?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,524 @@
+// src/header.rs
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::ops::Range;
+use std::path::Path;
+use std::process::Command;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::HeaderConfig;
+use crate::languages::{is_shebang, Language, Preamble};
+
+/// The built-in template. For block comments it is written as `Path: {path}`, with a space.
+pub const DEFAULT_TEMPLATE: &str = "Path:{path}";
+
+/// Older headers such as `// FILE: src/cli.rs`, always recognised as legacy.
+const FILE_TEMPLATE: &str = "FILE: {path}";
+
+/// Every placeholder a template may use.
+const PLACEHOLDERS: &[&str] = &["path", "filename", "module", "git_author", "date", "year"];
+/// Placeholders derived from the file's location; `check` compares these to spot stale headers.
+const PATH_PLACEHOLDERS: &[&str] = &["path", "filename", "module"];
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// A header as found in a file, split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedHeader {
+    /// The comment opener, e.g. `//`, `#`, `/*` or `<!--`.
+    pub prefix: String,
+    /// The block comment closer, e.g. `*/`, or empty for line comments.
+    pub suffix: String,
+    /// The placeholder values as written, e.g. `path` => `src/main.rs`.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl ParsedHeader {
+    /// Whether the header uses the comment style `add` would write for `language`.
+    pub fn has_style_of(&self, language: &Language) -> bool {
+        let (prefix, suffix) = language.header_delimiters();
+        self.prefix == prefix && self.suffix == suffix
+    }
+
+    /// Whether every path-derived field matches `display_path`, ignoring `/` vs `\` differences.
+    pub fn points_to(&self, display_path: &Path) -> bool {
+        PATH_PLACEHOLDERS.iter().all(|name| {
+            self.fields.get(*name).is_none_or(|value| {
+                normalize_separators(value) == normalize_separators(&path_value(name, display_path))
+            })
+        })
+    }
+}
+
+/// A header located inside a file's content.
+#[derive(Debug, Clone)]
+pub struct FoundHeader {
+    pub parsed: ParsedHeader,
+    /// The byte range of the header lines, including the last line ending.
+    pub range: Range<usize>,
+    /// The (0-based) line numbers of the header.
+    pub lines: Range<usize>,
+    /// Whether the header was written with one of the legacy templates.
+    pub outdated: bool,
+}
+
+/// How headers are written and recognised: the current template plus any legacy ones.
+#[derive(Debug, Clone)]
+pub struct HeaderFormat {
+    current: Template,
+    legacy: Vec<Template>,
+    /// Whether a blank line follows the header. It is then treated as part of the header.
+    blank_line: bool,
+}
+
+impl Default for HeaderFormat {
+    fn default() -> Self {
+        HeaderFormat::from_config(&HeaderConfig::default()).expect("built-in templates must be valid")
+    }
+}
+
+impl HeaderFormat {
+    /// Builds the format from the `[header]` config section. When a custom template is set,
+    /// the built-in one is always treated as legacy so `add --migrate` can upgrade it.
+    /// So is `FILE: {path}`, unless it is the current template.
+    pub fn from_config(config: &HeaderConfig) -> Result<Self> {
+        let current = match config.template.as_deref() {
+            Some(template) if template != DEFAULT_TEMPLATE => Template::new(template)?,
+            _ => Template::builtin(),
+        };
+        let mut legacy = Vec::new();
+        for template in config.legacy_templates.iter().flatten() {
+            legacy.push(if template == DEFAULT_TEMPLATE { Template::builtin() } else { Template::new(template)? });
+        }
+        if current.source != DEFAULT_TEMPLATE && legacy.iter().all(|t| t.source != DEFAULT_TEMPLATE) {
+            legacy.push(Template::builtin());
+        }
+        if legacy.iter().all(|t| t.source != FILE_TEMPLATE) {
+            legacy.push(Template::new(FILE_TEMPLATE)?);
+        }
+        legacy.retain(|t| t.source != current.source);
+        Ok(HeaderFormat { current, legacy, blank_line: config.blank_line.unwrap_or(false) })
+    }
+
+    /// Renders the header for `file_path`, one comment line per template line, without a trailing newline.
+    pub fn render(&self, language: &Language, file_path: &Path, display_path: &Path) -> String {
+        self.current.render(language, |name| match name {
+            "git_author" => git_author(file_path),
+            "date" => {
+                let (year, month, day) = today();
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            }
+            "year" => today().0.to_string(),
+            _ => path_value(name, display_path),
+        })
+    }
+
+    /// Renders the header as `add` inserts it: with its line ending and the blank line, if configured.
+    pub fn render_block(&self, language: &Language, file_path: &Path, display_path: &Path) -> String {
+        let separator = if self.blank_line_for(language) { "\n" } else { "" };
+        format!("{}\n{}", self.render(language, file_path, display_path), separator)
+    }
+
+    /// Whether a blank line follows the header in `language`: some languages need one regardless of the config.
+    fn blank_line_for(&self, language: &Language) -> bool {
+        self.blank_line || language.header_blank_line
+    }
+
+    /// Finds a header in the current or a legacy format, in any comment style, where `add` puts it.
+    pub fn find(&self, content: &str, language: &Language) -> Option<FoundHeader> {
+        let index = header_line_index(content, language);
+        let lines: Vec<&str> = content.lines().skip(index).collect();
+        std::iter::once(&self.current).chain(&self.legacy).enumerate().find_map(|(i, template)| {
+            let parsed = template.parse(&lines)?;
+            let mut len = template.parsers.len();
+            if self.blank_line_for(language) && lines.get(len).is_some_and(|line| line.trim().is_empty()) {
+                len += 1;
+            }
+            let lines = index..index + len;
+            let range = line_offset(content, lines.start)..line_offset(content, lines.end);
+            Some(FoundHeader { parsed, range, lines, outdated: i > 0 })
+        })
+    }
+}
+
+/// One header template, with the text for each comment line and a parser for each.
+#[derive(Debug, Clone)]
+struct Template {
+    source: String,
+    line_text: Vec<String>,
+    block_text: Vec<String>,
+    parsers: Vec<Regex>,
+}
+
+impl Template {
+    fn new(source: &str) -> Result<Self> {
+        let lines: Vec<String> = source.lines().map(|l| l.trim().to_string()).collect();
+        if lines.is_empty() || lines.iter().any(String::is_empty) {
+            bail!("Header template must not be empty or contain blank lines: {:?}", source);
+        }
+        for caps in PLACEHOLDER.captures_iter(source) {
+            if !PLACEHOLDERS.contains(&&caps[1]) {
+                bail!(
+                    "Unknown placeholder {} in header template {:?}. Available: {{{}}}",
+                    &caps[0],
+                    source,
+                    PLACEHOLDERS.join("}, {")
+                );
+            }
+        }
+        let parsers = lines.iter().map(|line| line_parser(line)).collect::<Result<_>>()?;
+        Ok(Template { source: source.to_string(), block_text: lines.clone(), line_text: lines, parsers })
+    }
+
+    fn builtin() -> Self {
+        let mut template = Template::new(DEFAULT_TEMPLATE).expect("built-in template must be valid");
+        template.block_text = vec!["Path: {path}".to_string()];
+        template
+    }
+
+    fn render(&self, language: &Language, value: impl Fn(&str) -> String) -> String {
+        let texts = if language.header_delimiters().1.is_empty() { &self.line_text } else { &self.block_text };
+        texts
+            .iter()
+            .map(|text| comment_line(language, &PLACEHOLDER.replace_all(text, |caps: &regex::Captures| value(&caps[1]))))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Parses the header from the first lines of `lines`, if they match this template.
+    fn parse(&self, lines: &[&str]) -> Option<ParsedHeader> {
+        if lines.len() < self.parsers.len() {
+            return None;
+        }
+        let mut parsed: Option<ParsedHeader> = None;
+        for (parser, line) in self.parsers.iter().zip(lines) {
+            let caps = parser.captures(line)?;
+            let header = parsed.get_or_insert_with(|| ParsedHeader {
+                prefix: caps["prefix"].to_string(),
+                suffix: caps.name("suffix").or(caps.name("closer")).map_or("", |m| m.as_str()).to_string(),
+                fields: BTreeMap::new(),
+            });
+            for name in parser.capture_names().flatten().filter(|n| PLACEHOLDERS.contains(n)) {
+                if let Some(m) = caps.name(name) {
+                    header.fields.entry(name.to_string()).or_insert_with(|| m.as_str().trim().to_string());
+                }
+            }
+        }
+        parsed
+    }
+}
+
+/// Wraps `text` in the comment style used for headers in `language`, e.g. `// text` or `/* text */`.
+pub fn comment_line(language: &Language, text: &str) -> String {
+    let (prefix, suffix) = language.header_delimiters();
+    // Only add a space before the suffix if the suffix is not empty.
+    if suffix.is_empty() {
+        format!("{} {}", prefix, text).trim().to_string()
+    } else {
+        format!("{} {} {}", prefix, text, suffix).trim().to_string()
+    }
+}
+
+/// Builds a regex for one template line, accepting any comment opener and closer
+/// and any amount of whitespace wherever the template has some.
+fn line_parser(text: &str) -> Result<Regex> {
+    let mut pattern = String::from(r"^\s*(?P<prefix>[^\w\s]+)\s*");
+    let mut seen = Vec::new();
+    let mut last = 0;
+    for caps in PLACEHOLDER.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        push_literal(&mut pattern, &text[last..whole.start()]);
+        let name = caps.get(1).unwrap().as_str();
+        let body = if PATH_PLACEHOLDERS.contains(&name) { ".+?" } else { ".*?" };
+        if seen.contains(&name) {
+            pattern.push_str(&format!("(?:{})", body));
+        } else {
+            pattern.push_str(&format!("(?P<{}>{})", name, body));
+            seen.push(name);
+        }
+        last = whole.end();
+    }
+    push_literal(&mut pattern, &text[last..]);
+    pattern.push_str(r"(?:\s+(?P<suffix>[^\w\s]+)|(?P<closer>\*/|-->|-\}|\*\)|\]\]))?\s*$");
+    Regex::new(&pattern).with_context(|| format!("Invalid header template line: {:?}", text))
+}
+
+fn push_literal(pattern: &mut String, literal: &str) {
+    let mut in_space = false;
+    for c in literal.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                pattern.push_str(r"\s*");
+            }
+            in_space = true;
+        } else {
+            pattern.push_str(&regex::escape(&c.to_string()));
+            in_space = false;
+        }
+    }
+}
+
+/// The value of a path-derived placeholder for a file shown as `display_path`.
+fn path_value(name: &str, display_path: &Path) -> String {
+    match name {
+        "filename" => display_path.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned()),
+        // `src/api/user.py` => `src.api.user`
+        "module" => display_path
+            .with_extension("")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("."),
+        _ => display_path.display().to_string(),
+    }
+}
+
+/// The author of the last commit touching the file, or the configured git user for new files.
+fn git_author(file_path: &Path) -> String {
+    let dir = file_path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let run = |args: &[&OsStr]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let name = file_path.file_name().unwrap_or_default();
+    run(&["log".as_ref(), "-1".as_ref(), "--format=%an".as_ref(), "--".as_ref(), name])
+        .or_else(|| run(&["config".as_ref(), "user.name".as_ref()]))
+        .unwrap_or_default()
+}
+
+/// Today's UTC date as `(year, month, day)`. Honours `SOURCE_DATE_EPOCH` for reproducible output.
+pub fn today() -> (i64, u32, u32) {
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64));
+    civil_from_days(seconds.div_euclid(86_400))
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's algorithm).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn normalize_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// Returns the index of the line the path header belongs on: after a shebang and any
+/// of the language's preamble constructs (encoding lines, `<?xml ...?>`, front matter, ...).
+pub fn header_line_index(content: &str, language: &Language) -> usize {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut index = usize::from(lines.first().is_some_and(|line| is_shebang(line)));
+    while let Some(len) = language.preamble.iter().find_map(|rule| preamble_len(*rule, &lines, index)) {
+        index += len;
+    }
+    index
+}
+
+/// How many lines the construct `rule` spans starting at line `index`, if it is there.
+fn preamble_len(rule: Preamble, lines: &[&str], index: usize) -> Option<usize> {
+    let line = lines.get(index)?.trim_start_matches('\u{feff}');
+    let trimmed = line.trim();
+    match rule {
+        Preamble::XmlDeclaration if index == 0 && trimmed.starts_with("<?xml") => {
+            let end = lines[index..].iter().position(|l| l.contains("?>"))?;
+            Some(end + 1)
+        }
+        Preamble::PhpOpenTag if trimmed.starts_with("<?php") && !trimmed.contains("?>") => Some(1),
+        Preamble::EncodingLine => {
+            let comment = trimmed.strip_prefix('#')?;
+            let is_magic = comment.contains("coding:")
+                || comment.contains("coding=")
+                || comment.trim_start().starts_with("frozen_string_literal:");
+            is_magic.then_some(1)
+        }
+        Preamble::GoBuild => {
+            let is_constraint = |l: &&&str| l.starts_with("//go:build") || l.starts_with("// +build");
+            let constraints = lines[index..].iter().take_while(is_constraint).count();
+            if constraints == 0 {
+                return None;
+            }
+            let blanks = lines[index + constraints..].iter().take_while(|l| l.trim().is_empty()).count();
+            Some(constraints + blanks)
+        }
+        Preamble::Directives => {
+            let body = trimmed.trim_end_matches(';');
+            let quote = body.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let inner = body.strip_prefix(quote)?.strip_suffix(quote)?;
+            let is_directive = inner.starts_with("use ") && inner.chars().all(|c| c.is_ascii_alphabetic() || c == ' ');
+            is_directive.then_some(1)
+        }
+        Preamble::FrontMatter if index == 0 && (trimmed == "---" || trimmed == "+++") => {
+            let closers: &[&str] = if trimmed == "---" { &["---", "..."] } else { &["+++"] };
+            let end = lines[1..].iter().position(|l| closers.contains(&l.trim()))?;
+            Some(end + 2)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the byte offset at which line `index` (0-based) of `content` starts.
+pub fn line_offset(content: &str, index: usize) -> usize {
+    content.split_inclusive('\n').take(index).map(str::len).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageRegistry;
+    use std::path::PathBuf;
+
+    fn parse(format: &HeaderFormat, content: &str) -> Option<(String, String, Option<String>)> {
+        let rust = LanguageRegistry::builtin().language_for(Path::new("a.rs")).clone();
+        let found = format.find(content, &rust)?;
+        Some((found.parsed.prefix, found.parsed.suffix, found.parsed.fields.get("path").cloned()))
+    }
+
+    fn parsed(prefix: &str, suffix: &str, path: &str) -> Option<(String, String, Option<String>)> {
+        Some((prefix.to_string(), suffix.to_string(), Some(path.to_string())))
+    }
+
+    fn custom(template: &str) -> HeaderFormat {
+        HeaderFormat::from_config(&HeaderConfig { template: Some(template.to_string()), ..Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn test_parse_default_header_styles() {
+        let format = HeaderFormat::default();
+        assert_eq!(parse(&format, "// Path:src/main.rs"), parsed("//", "", "src/main.rs"));
+        assert_eq!(parse(&format, "  #  Path: a b/c.py  "), parsed("#", "", "a b/c.py"));
+        assert_eq!(parse(&format, "/* Path: web/app.css */"), parsed("/*", "*/", "web/app.css"));
+        assert_eq!(parse(&format, "<!-- Path:index.html-->"), parsed("<!--", "-->", "index.html"));
+        assert_eq!(parse(&format, "// Path:lib/a.c++"), parsed("//", "", "lib/a.c++"));
+        assert_eq!(parse(&format, "let x = 1; // Path: nope"), None);
+        assert_eq!(parse(&format, "// Path:"), None);
+    }
+
+    #[test]
+    fn test_render_and_parse_round_trip() {
+        let registry = LanguageRegistry::builtin();
+        for format in [HeaderFormat::default(), custom("File: {filename} ({module})\nAuthor: {git_author}, {date}")] {
+            for file in ["a.rs", "a.py", "a.css", "a.html"] {
+                let language = registry.language_for(Path::new(file));
+                let display = PathBuf::from("src").join(file);
+                let content = format!("{}\nbody\n", format.render(language, Path::new(file), &display));
+                let found = format.find(&content, language).unwrap();
+                assert!(!found.outdated);
+                assert_eq!(&content[found.range.end..], "body\n");
+                assert!(found.parsed.has_style_of(language), "{}", file);
+                assert!(found.parsed.points_to(&display), "{}", file);
+                assert!(!found.parsed.points_to(Path::new("b.rs")), "{}", file);
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_format_is_unchanged() {
+        let registry = LanguageRegistry::builtin();
+        let render = |file: &str| HeaderFormat::default().render(registry.language_for(Path::new(file)), Path::new(file), Path::new(file));
+        assert_eq!(render("a.rs"), "// Path:a.rs");
+        assert_eq!(render("a.css"), "/* Path: a.css */");
+        assert_eq!(render("a.html"), "<!-- Path: a.html -->");
+    }
+
+    #[test]
+    fn test_custom_template_recognises_legacy_headers() {
+        let format = custom("File: {path}");
+        let rust = LanguageRegistry::builtin().language_for(Path::new("a.rs")).clone();
+        assert_eq!(format.render(&rust, Path::new("a.rs"), Path::new("src/a.rs")), "// File: src/a.rs");
+        assert!(!format.find("// File: src/a.rs\n", &rust).unwrap().outdated);
+        let legacy = format.find("// Path:src/a.rs\n", &rust).unwrap();
+        assert!(legacy.outdated);
+        assert_eq!(legacy.parsed.fields["path"], "src/a.rs");
+    }
+
+    #[test]
+    fn test_file_headers_are_legacy() {
+        let format = HeaderFormat::default();
+        let rust = LanguageRegistry::builtin().language_for(Path::new("a.rs")).clone();
+        let found = format.find("// FILE: src/cli.rs\n\nuse clap;\n", &rust).unwrap();
+        assert!(found.outdated);
+        assert_eq!(found.parsed.fields["path"], "src/cli.rs");
+        assert_eq!(found.lines, 0..1);
+        assert!(!custom("FILE: {path}").find("// FILE: src/cli.rs\n", &rust).unwrap().outdated);
+    }
+
+    #[test]
+    fn test_blank_line_belongs_to_the_header() {
+        let config = HeaderConfig { blank_line: Some(true), ..Default::default() };
+        let format = HeaderFormat::from_config(&config).unwrap();
+        let rust = LanguageRegistry::builtin().language_for(Path::new("a.rs")).clone();
+        assert_eq!(format.render_block(&rust, Path::new("a.rs"), Path::new("a.rs")), "// Path:a.rs\n\n");
+        let content = "// Path:a.rs\n  \nfn main() {}\n";
+        assert_eq!(&content[format.find(content, &rust).unwrap().range.end..], "fn main() {}\n");
+        // A header without one is still found.
+        assert_eq!(format.find("// Path:a.rs\nfn main() {}\n", &rust).unwrap().lines, 0..1);
+    }
+
+    #[test]
+    fn test_invalid_templates_are_rejected() {
+        let config = |template: &str| HeaderConfig { template: Some(template.to_string()), ..Default::default() };
+        assert!(HeaderFormat::from_config(&config("File: {nope}")).is_err());
+        assert!(HeaderFormat::from_config(&config("")).is_err());
+        assert!(HeaderFormat::from_config(&config("a\n\nb")).is_err());
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn test_find_header_after_shebang() {
+        let shell = LanguageRegistry::builtin().language_for(Path::new("run.sh")).clone();
+        let content = "#!/bin/sh\n# Path:run.sh\necho\n";
+        let found = HeaderFormat::default().find(content, &shell).unwrap();
+        assert_eq!(found.parsed.fields["path"], "run.sh");
+        assert_eq!(&content[found.range], "# Path:run.sh\n");
+        assert!(HeaderFormat::default().find("echo\n# Path:run.sh\n", &shell).is_none());
+    }
+
+    #[test]
+    fn test_header_line_index_skips_preamble() {
+        let registry = LanguageRegistry::builtin();
+        let index = |file: &str, content: &str| header_line_index(content, registry.language_for(Path::new(file)));
+
+        assert_eq!(index("a.py", "#!/usr/bin/env python\n# -*- coding: utf-8 -*-\nimport os\n"), 2);
+        assert_eq!(index("a.py", "# vim: set fileencoding=utf-8 :\nimport os\n"), 1);
+        assert_eq!(index("a.py", "# a comment\n# -*- coding: utf-8 -*-\n"), 0);
+        assert_eq!(index("a.rb", "# frozen_string_literal: true\nputs 1\n"), 1);
+        assert_eq!(index("a.xml", "<?xml version=\"1.0\"\n  encoding=\"UTF-8\"?>\n<root/>\n"), 2);
+        assert_eq!(index("a.php", "#!/usr/bin/php\n<?php\necho 1;\n"), 2);
+        assert_eq!(index("a.php", "<?php echo 1; ?>\n<p>hi</p>\n"), 0);
+        assert_eq!(index("a.go", "//go:build linux\n// +build linux\n\npackage main\n"), 3);
+        assert_eq!(index("a.ts", "'use client';\n\"use strict\"\nimport x from 'x';\n"), 2);
+        assert_eq!(index("a.js", "'use my own thing'; foo();\n"), 0);
+        assert_eq!(index("a.md", "---\ntitle: Hi\n---\n# Hi\n"), 3);
+        assert_eq!(index("a.md", "+++\ntitle = 1\n+++\n"), 3);
+        assert_eq!(index("a.md", "---\nno closing fence\n"), 0);
+        // Rules only apply to the languages that declare them.
+        assert_eq!(index("a.rs", "// -*- coding: utf-8 -*-\n"), 0);
+        assert_eq!(index("a.yaml", "---\na: 1\n---\n"), 0);
+    }
+}
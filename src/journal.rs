@@ -0,0 +1,293 @@
+// src/journal.rs
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::header::civil_from_days;
+
+/// One change made by a run, with what `undo` needs to revert it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Entry {
+    /// An existing file was overwritten.
+    Modified {
+        path: PathBuf,
+        /// The content before the run.
+        original: String,
+        /// A hash of the content the run wrote, to spot edits made since.
+        written: String,
+    },
+    /// A new file was created.
+    CreatedFile { path: PathBuf, written: String },
+    /// A new directory was created.
+    CreatedDir { path: PathBuf },
+}
+
+impl Entry {
+    pub fn path(&self) -> &Path {
+        match self {
+            Entry::Modified { path, .. } | Entry::CreatedFile { path, .. } | Entry::CreatedDir { path } => path,
+        }
+    }
+}
+
+/// The record of one mutating run, stored as `<state dir>/journal/<id>.jsonl`.
+///
+/// The first line holds the run itself and every following line one entry. Entries
+/// are appended before the change they describe is made, so a run that is killed
+/// halfway can still be undone.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Journal {
+    pub id: String,
+    pub command: String,
+    pub directory: PathBuf,
+    /// Unix time of the first change.
+    pub started: u64,
+    #[serde(default)]
+    pub undone: bool,
+    #[serde(skip)]
+    pub entries: Vec<Entry>,
+    /// The open journal file, created with the first entry.
+    #[serde(skip)]
+    file: Option<fs::File>,
+    /// The directory holding the journal file, if not the default `journal_dir()`.
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+}
+
+impl Journal {
+    pub fn new(command: &str, directory: &Path) -> Self {
+        Journal {
+            id: String::new(),
+            command: command.to_string(),
+            directory: absolute(directory),
+            started: 0,
+            undone: false,
+            entries: Vec::new(),
+            file: None,
+            dir: None,
+        }
+    }
+
+    fn dir(&self) -> Result<PathBuf> {
+        self.dir.clone().map_or_else(journal_dir, Ok)
+    }
+
+    /// Appends `entry` to the journal file, creating the file first if needed.
+    fn push(&mut self, entry: Entry) -> Result<()> {
+        if self.file.is_none() {
+            self.started = now();
+            self.file = Some(self.create()?);
+        }
+        let file = self.file.as_mut().expect("journal file was just created");
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+            .and_then(|_| file.sync_data())
+            .with_context(|| format!("Failed to write journal of run {}", self.id))?;
+        self.merge(entry);
+        Ok(())
+    }
+
+    /// Adds `entry` to the in-memory list. A file written twice keeps its first original.
+    fn merge(&mut self, entry: Entry) {
+        if let Entry::Modified { path, written, .. } = &entry {
+            for existing in &mut self.entries {
+                match existing {
+                    Entry::Modified { path: p, written: w, .. } | Entry::CreatedFile { path: p, written: w } if p == path => {
+                        *w = written.clone();
+                        return;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.entries.push(entry);
+    }
+
+    /// Records that `path`, which holds `original`, is about to hold `written`.
+    pub fn record_write(&mut self, path: &Path, original: &str, written: &str) -> Result<()> {
+        self.push(Entry::Modified { path: absolute(path), original: original.to_string(), written: content_hash(written) })
+    }
+
+    pub fn record_created_file(&mut self, path: &Path, written: &str) -> Result<()> {
+        self.push(Entry::CreatedFile { path: absolute(path), written: content_hash(written) })
+    }
+
+    pub fn record_created_dir(&mut self, path: &Path) -> Result<()> {
+        self.push(Entry::CreatedDir { path: absolute(path) })
+    }
+
+    /// Creates the journal file under a fresh id and writes the run line.
+    fn create(&mut self) -> Result<fs::File> {
+        let dir = self.dir()?;
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create journal directory: {}", dir.display()))?;
+        let base = run_id(self.started);
+        // Runs started in the same second, e.g. by `watch`, get a suffix.
+        let mut n = 1;
+        loop {
+            self.id = if n == 1 { base.clone() } else { format!("{}-{}", base, n) };
+            n += 1;
+            let path = dir.join(format!("{}.jsonl", self.id));
+            match fs::OpenOptions::new().append(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    writeln!(file, "{}", serde_json::to_string(self)?)
+                        .with_context(|| format!("Failed to write journal: {}", path.display()))?;
+                    return Ok(file);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e).with_context(|| format!("Failed to create journal: {}", path.display())),
+            }
+        }
+    }
+
+    /// Closes the journal file and returns the run id. Returns `None` if nothing changed.
+    pub fn close(&mut self) -> Option<String> {
+        self.file.take().map(|_| self.id.clone())
+    }
+
+    /// Rewrites an already saved journal, e.g. after marking it undone.
+    pub fn write(&self) -> Result<()> {
+        let path = self.dir()?.join(format!("{}.jsonl", self.id));
+        let mut content = serde_json::to_string(self)? + "\n";
+        for entry in &self.entries {
+            content += &serde_json::to_string(entry)?;
+            content.push('\n');
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write journal: {}", path.display()))
+    }
+
+    pub fn load(id: &str) -> Result<Self> {
+        Journal::load_from(&journal_dir()?, id)
+    }
+
+    fn load_from(dir: &Path, id: &str) -> Result<Self> {
+        let path = dir.join(format!("{}.jsonl", id));
+        if !path.exists() {
+            bail!("No run with id {:?}. Run `filedress history` to list them.", id);
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read journal: {}", path.display()))?;
+        let mut lines = content.lines();
+        let mut journal: Journal = serde_json::from_str(lines.next().unwrap_or_default())
+            .with_context(|| format!("Failed to parse journal: {}", path.display()))?;
+        let lines: Vec<&str> = lines.collect();
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(entry) => journal.merge(entry),
+                // A run killed while appending leaves a partial last line; the change it
+                // describes was never made.
+                Err(_) if i + 1 == lines.len() && !content.ends_with('\n') => {}
+                Err(e) => return Err(e).with_context(|| format!("Failed to parse journal: {}", path.display())),
+            }
+        }
+        journal.dir = Some(dir.to_path_buf());
+        Ok(journal)
+    }
+
+    /// All saved runs, newest first. Journals that can't be read are skipped with a warning.
+    pub fn all() -> Result<Vec<Self>> {
+        let dir = journal_dir()?;
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut runs = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if let Some(id) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".jsonl")) {
+                match Journal::load_from(&dir, id) {
+                    Ok(run) => runs.push(run),
+                    Err(e) => eprintln!("Warning: skipping journal {}: {:#}", path.display(), e),
+                }
+            }
+        }
+        runs.sort_by(|a, b| (b.started, &b.id).cmp(&(a.started, &a.id)));
+        Ok(runs)
+    }
+
+    /// Whether the run was made in `dir` or one of its subdirectories.
+    pub fn is_within(&self, dir: &Path) -> bool {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        canonical(&self.directory).starts_with(canonical(dir))
+    }
+}
+
+/// Where journals are kept: `FILEDRESS_STATE_DIR`, or the platform's local data directory.
+pub fn state_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("FILEDRESS_STATE_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs::data_local_dir()
+        .map(|dir| dir.join("filedress"))
+        .context("Could not find a data directory; set FILEDRESS_STATE_DIR")
+}
+
+fn journal_dir() -> Result<PathBuf> {
+    Ok(state_dir()?.join("journal"))
+}
+
+/// A stable (FNV-1a) hash of `content`, as hex.
+pub fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// A sortable id like `20261016-142301` for a run started at Unix time `secs`.
+fn run_id(secs: u64) -> String {
+    let secs = secs as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let time = secs.rem_euclid(86_400);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_id_and_hash() {
+        assert_eq!(run_id(0), "19700101-000000");
+        assert_eq!(run_id(1_792_160_581), "20261016-142301");
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_ne!(content_hash("a\n"), content_hash("a\r\n"));
+    }
+
+    #[test]
+    fn test_repeated_writes_keep_the_first_original() -> Result<()> {
+        let state = tempfile::tempdir()?;
+        let mut journal = Journal { dir: Some(state.path().to_path_buf()), ..Journal::new("watch", Path::new("/tmp")) };
+        journal.record_write(Path::new("/tmp/a.rs"), "one", "two")?;
+        journal.record_write(Path::new("/tmp/a.rs"), "two", "three")?;
+        let expected = vec![Entry::Modified { path: PathBuf::from("/tmp/a.rs"), original: "one".into(), written: content_hash("three") }];
+        assert_eq!(journal.entries, expected);
+
+        // The journal is on disk before the run finishes.
+        let id = journal.id.clone();
+        assert_eq!(Journal::load_from(state.path(), &id)?.entries, expected);
+        assert_eq!(journal.close(), Some(id));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_skips_a_partial_last_entry() -> Result<()> {
+        let state = tempfile::tempdir()?;
+        let path = state.path().join("test-partial.jsonl");
+        let run = r#"{"id":"test-partial","command":"add","directory":"/tmp","started":1}"#;
+        let entry = r#"{"kind":"created-dir","path":"/tmp/a"}"#;
+        fs::write(&path, format!("{}\n{}\n{{\"kind\":\"modi", run, entry))?;
+        let journal = Journal::load_from(state.path(), "test-partial")?;
+        assert_eq!(journal.entries, vec![Entry::CreatedDir { path: PathBuf::from("/tmp/a") }]);
+        Ok(())
+    }
+}
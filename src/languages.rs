@@ -0,0 +1,719 @@
+// src/languages.rs
+
+use anyhow::{bail, Context, Result};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::cli::ProjectType;
+use crate::config::LanguageConfig;
+
+/// Which comment form a language uses for the `Path:` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderStyle {
+    /// `// Path:src/main.rs` — falls back to `Block` if the language has no line comment.
+    Line,
+    /// `/* Path: src/main.css */`
+    Block,
+}
+
+/// A construct that must stay at the top of a file, so the `Path:` header goes after it.
+///
+/// A shebang is always kept first, whatever the language.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preamble {
+    /// `<?xml version="1.0"?>` on the first line.
+    XmlDeclaration,
+    /// A `<?php` opening tag.
+    PhpOpenTag,
+    /// Encoding and magic comments, e.g. `# -*- coding: utf-8 -*-` or `# frozen_string_literal: true`.
+    EncodingLine,
+    /// Go `//go:build` and `// +build` constraints, and the blank line after them.
+    GoBuild,
+    /// JavaScript directives such as `"use strict";` or `'use client'`.
+    Directives,
+    /// A `---` (YAML) or `+++` (TOML) front matter block at the start of the file.
+    FrontMatter,
+}
+
+/// Everything filedress knows about the comment and string syntax of one language.
+#[derive(Clone, Debug)]
+pub struct Language {
+    pub name: String,
+    pub extensions: Vec<String>,
+    /// Exact file names (e.g. `Dockerfile`) that belong to this language.
+    pub filenames: Vec<String>,
+    /// Globs matched against the file name (e.g. `.env.*`).
+    pub filename_patterns: Vec<GlobMatcher>,
+    /// Shebang interpreters (e.g. `python` matches `#!/usr/bin/env python3`).
+    pub interpreters: Vec<String>,
+    /// Extra names accepted in vim/emacs modelines, besides `name`.
+    pub aliases: Vec<String>,
+    pub line_comment: Option<String>,
+    pub block_comment: Option<(String, String)>,
+    /// Whether block comments can be nested (`/* a /* b */ c */`).
+    pub nested_comments: bool,
+    /// String delimiters, longest first (e.g. `"""` before `"`).
+    pub string_delimiters: Vec<String>,
+    /// String delimiters whose content has no escapes (e.g. Go's backtick raw strings).
+    pub raw_strings: Vec<String>,
+    /// Openers and closers of unquoted tokens taken as-is, like strings (e.g. CSS `url(` and `)`).
+    pub raw_tokens: Vec<(String, String)>,
+    /// Comment openers that mark documentation (e.g. `///`, `/**`).
+    pub doc_comments: Vec<String>,
+    /// Whether `clean` keeps doc comments, if set for this language in the config.
+    pub keep_docs: Option<bool>,
+    /// Patterns for comments that tools act on (e.g. `eslint-disable`, `noqa`), matched against
+    /// the comment's text without its delimiters. `clean` keeps these.
+    pub directives: Vec<Regex>,
+    pub header_style: HeaderStyle,
+    /// Constructs that must come before the `Path:` header.
+    pub preamble: Vec<Preamble>,
+    /// Whether the header always needs a blank line after it, e.g. so Go doesn't take it for the package doc.
+    pub header_blank_line: bool,
+    /// The `--project` presets this language belongs to.
+    pub presets: Vec<ProjectType>,
+}
+
+impl Language {
+    fn new(name: &str, extensions: &[&str]) -> Self {
+        Language {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|s| s.to_string()).collect(),
+            filenames: Vec::new(),
+            filename_patterns: Vec::new(),
+            interpreters: Vec::new(),
+            aliases: Vec::new(),
+            line_comment: None,
+            block_comment: None,
+            nested_comments: false,
+            string_delimiters: Vec::new(),
+            raw_strings: Vec::new(),
+            raw_tokens: Vec::new(),
+            doc_comments: Vec::new(),
+            keep_docs: None,
+            directives: Vec::new(),
+            header_style: HeaderStyle::Line,
+            preamble: Vec::new(),
+            header_blank_line: false,
+            presets: Vec::new(),
+        }
+    }
+
+    fn filenames(mut self, names: &[&str]) -> Self {
+        self.filenames = names.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn patterns(mut self, patterns: &[&str]) -> Self {
+        self.filename_patterns = patterns
+            .iter()
+            .map(|p| Glob::new(p).expect("built-in filename pattern must be valid").compile_matcher())
+            .collect();
+        self
+    }
+
+    fn interpreters(mut self, interpreters: &[&str]) -> Self {
+        self.interpreters = interpreters.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn aliases(mut self, aliases: &[&str]) -> Self {
+        self.aliases = aliases.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn line(mut self, prefix: &str) -> Self {
+        self.line_comment = Some(prefix.to_string());
+        self
+    }
+
+    fn block(mut self, start: &str, end: &str) -> Self {
+        self.block_comment = Some((start.to_string(), end.to_string()));
+        self
+    }
+
+    fn nested(mut self) -> Self {
+        self.nested_comments = true;
+        self
+    }
+
+    fn strings(mut self, delimiters: &[&str]) -> Self {
+        self.string_delimiters = delimiters.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn raw_strings(mut self, delimiters: &[&str]) -> Self {
+        self.raw_strings = delimiters.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn raw_tokens(mut self, tokens: &[(&str, &str)]) -> Self {
+        self.raw_tokens = tokens.iter().map(|(start, end)| (start.to_string(), end.to_string())).collect();
+        self
+    }
+
+    fn docs(mut self, markers: &[&str]) -> Self {
+        self.doc_comments = markers.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn directives(mut self, patterns: &[&str]) -> Self {
+        self.directives = patterns.iter().map(|p| Regex::new(p).expect("built-in directive pattern must be valid")).collect();
+        self
+    }
+
+    fn block_header(mut self) -> Self {
+        self.header_style = HeaderStyle::Block;
+        self
+    }
+
+    fn preamble(mut self, rules: &[Preamble]) -> Self {
+        self.preamble = rules.to_vec();
+        self
+    }
+
+    fn header_blank_line(mut self) -> Self {
+        self.header_blank_line = true;
+        self
+    }
+
+    fn preset(mut self, preset: ProjectType) -> Self {
+        self.presets.push(preset);
+        self
+    }
+
+    /// Overlays the fields set in a `[languages.<name>]` config section.
+    fn apply_config(&mut self, config: &LanguageConfig) -> Result<()> {
+        if let Some(extensions) = &config.extensions {
+            self.extensions = extensions.clone();
+        }
+        if let Some(filenames) = &config.filenames {
+            self.filenames = filenames.clone();
+        }
+        if let Some(patterns) = &config.filename_patterns {
+            self.filename_patterns = patterns
+                .iter()
+                .map(|p| {
+                    Glob::new(p)
+                        .map(|g| g.compile_matcher())
+                        .with_context(|| format!("Invalid filename pattern for language '{}': {}", self.name, p))
+                })
+                .collect::<Result<_>>()?;
+        }
+        if let Some(interpreters) = &config.interpreters {
+            self.interpreters = interpreters.clone();
+        }
+        if let Some(aliases) = &config.aliases {
+            self.aliases = aliases.clone();
+        }
+        if let Some(prefix) = &config.line_comment {
+            self.line_comment = Some(prefix.clone());
+        }
+        if let Some([start, end]) = &config.block_comment {
+            self.block_comment = Some((start.clone(), end.clone()));
+        }
+        if let Some(nested) = config.nested_comments {
+            self.nested_comments = nested;
+        }
+        if let Some(strings) = &config.strings {
+            self.string_delimiters = strings.clone();
+            // Longest first, so `"""` is tried before `"`.
+            self.string_delimiters.sort_by_key(|d| std::cmp::Reverse(d.chars().count()));
+        }
+        if let Some(raw_strings) = &config.raw_strings {
+            self.raw_strings = raw_strings.clone();
+        }
+        if let Some(tokens) = &config.raw_tokens {
+            self.raw_tokens = tokens.iter().map(|[start, end]| (start.clone(), end.clone())).collect();
+        }
+        if let Some(docs) = &config.doc_comments {
+            self.doc_comments = docs.clone();
+        }
+        if let Some(keep_docs) = config.keep_docs {
+            self.keep_docs = Some(keep_docs);
+        }
+        if let Some(directives) = &config.directives {
+            self.directives = directives
+                .iter()
+                .map(|p| Regex::new(p).with_context(|| format!("Invalid directive pattern for language '{}': {}", self.name, p)))
+                .collect::<Result<_>>()?;
+        }
+        if let Some(style) = config.header_style {
+            self.header_style = style;
+        }
+        if let Some(preamble) = &config.preamble {
+            self.preamble = preamble.clone();
+        }
+        if let Some(blank_line) = config.header_blank_line {
+            self.header_blank_line = blank_line;
+        }
+        Ok(())
+    }
+
+    /// Whether `interpreter` (e.g. `python3.11`) runs this language, ignoring version suffixes.
+    fn runs_with(&self, interpreter: &str) -> bool {
+        let unversioned = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '-');
+        self.interpreters.iter().any(|i| i == interpreter || i == unversioned)
+    }
+
+    /// Whether a vim `ft=`/emacs `mode:` name refers to this language.
+    fn is_called(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether this language claims the file by its name rather than its extension.
+    fn matches_file_name(&self, name: &str) -> bool {
+        self.filenames.iter().any(|f| f == name) || self.filename_patterns.iter().any(|p| p.is_match(name))
+    }
+
+    /// Whether a comment with this text (delimiters stripped) is a directive that tools act on.
+    pub fn is_directive(&self, body: &str) -> bool {
+        self.directives.iter().any(|directive| directive.is_match(body))
+    }
+
+    /// Returns the `(prefix, suffix)` pair used to wrap the path header.
+    pub fn header_delimiters(&self) -> (&str, &str) {
+        match (&self.line_comment, &self.block_comment, self.header_style) {
+            (_, Some((start, end)), HeaderStyle::Block) => (start, end),
+            (Some(prefix), _, _) => (prefix, ""),
+            (None, Some((start, end)), _) => (start, end),
+            (None, None, _) => ("//", ""),
+        }
+    }
+}
+
+/// The set of languages known to filedress.
+#[derive(Clone, Debug)]
+pub struct LanguageRegistry {
+    languages: Vec<Language>,
+    fallback: Language,
+}
+
+impl LanguageRegistry {
+    /// The registry of languages that ship with filedress.
+    pub fn builtin() -> Self {
+        LanguageRegistry {
+            languages: builtin_languages(),
+            fallback: Language::new("default", &[]).line("//"),
+        }
+    }
+
+    /// The built-in registry with user-defined languages merged in.
+    ///
+    /// A section whose name matches a built-in language overrides only the fields it sets.
+    /// User languages are consulted before built-ins, so they can claim existing extensions.
+    pub fn with_user_languages(user_languages: &BTreeMap<String, LanguageConfig>) -> Result<Self> {
+        let mut registry = LanguageRegistry::builtin();
+        let mut merged = Vec::new();
+
+        for (name, config) in user_languages {
+            let mut language = match registry.languages.iter().position(|l| &l.name == name) {
+                Some(idx) => registry.languages.remove(idx),
+                None => Language::new(name, &[]),
+            };
+            language.apply_config(config)?;
+
+            if language.line_comment.is_none() && language.block_comment.is_none() {
+                bail!("Language '{}' in config must define `line_comment` or `block_comment`", name);
+            }
+            if language.extensions.is_empty() && language.filenames.is_empty() && language.filename_patterns.is_empty() {
+                bail!("Language '{}' in config must define `extensions`, `filenames` or `filename_patterns`", name);
+            }
+            merged.push(language);
+        }
+
+        merged.append(&mut registry.languages);
+        registry.languages = merged;
+        Ok(registry)
+    }
+
+    pub fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
+    /// Finds the language for a file, if it is one we know.
+    ///
+    /// Exact file names and file name patterns (`Makefile`, `.env.*`) are checked before
+    /// extensions, so `CMakeLists.txt` is CMake rather than plain text.
+    pub fn detect(&self, path: &Path) -> Option<&Language> {
+        self.detect_by_name(path).or_else(|| {
+            let ext = path.extension().and_then(|s| s.to_str())?;
+            self.languages.iter().find(|lang| lang.extensions.iter().any(|e| e == ext))
+        })
+    }
+
+    /// Finds a language that claims the file by its name alone.
+    pub fn detect_by_name(&self, path: &Path) -> Option<&Language> {
+        let name = path.file_name().and_then(|s| s.to_str())?;
+        self.languages.iter().find(|lang| lang.matches_file_name(name))
+    }
+
+    /// Like `detect`, but when the name and extension are unknown, falls back to
+    /// reading the file's shebang line or a vim/emacs modeline.
+    pub fn detect_file(&self, path: &Path) -> Option<&Language> {
+        self.detect(path).or_else(|| self.sniff(path))
+    }
+
+    /// Finds a language that claims the file by name, or by content if its extension is unknown.
+    /// Unlike `detect_file`, a plain extension match returns `None`.
+    pub fn detect_by_name_or_content(&self, path: &Path) -> Option<&Language> {
+        if let Some(lang) = self.detect_by_name(path) {
+            return Some(lang);
+        }
+        if self.detect(path).is_some() {
+            return None;
+        }
+        self.sniff(path)
+    }
+
+    /// Like `detect_file`, but falls back to `//` comments for unknown files.
+    pub fn language_for(&self, path: &Path) -> &Language {
+        self.detect_file(path).unwrap_or(&self.fallback)
+    }
+
+    /// Reads the start of a file and detects its language from the content.
+    fn sniff(&self, path: &Path) -> Option<&Language> {
+        let mut head = Vec::with_capacity(SNIFF_BYTES);
+        File::open(path).ok()?.take(SNIFF_BYTES as u64).read_to_end(&mut head).ok()?;
+        if head.contains(&0) {
+            return None; // Binary file
+        }
+        self.detect_content(&String::from_utf8_lossy(&head))
+    }
+
+    /// Detects a language from a shebang on the first line, or a modeline in the first few lines.
+    pub fn detect_content(&self, head: &str) -> Option<&Language> {
+        let mut lines = head.lines();
+        if let Some(interpreter) = lines.next().and_then(shebang_interpreter)
+            && let Some(lang) = self.languages.iter().find(|lang| lang.runs_with(interpreter))
+        {
+            return Some(lang);
+        }
+        head.lines()
+            .take(MODELINE_LINES)
+            .filter_map(modeline_language)
+            .find_map(|name| self.languages.iter().find(|lang| lang.is_called(name)))
+    }
+
+    /// Every extension handled by some language, in registry order.
+    pub fn all_extensions(&self) -> Vec<String> {
+        let mut exts: Vec<String> = Vec::new();
+        for ext in self.languages.iter().flat_map(|lang| &lang.extensions) {
+            if !exts.contains(ext) {
+                exts.push(ext.clone());
+            }
+        }
+        exts
+    }
+
+    /// The extensions of every language belonging to a `--project` preset.
+    pub fn preset_extensions(&self, preset: &ProjectType) -> Vec<String> {
+        self.languages
+            .iter()
+            .filter(|lang| lang.presets.contains(preset))
+            .flat_map(|lang| lang.extensions.iter().cloned())
+            .collect()
+    }
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> Self {
+        LanguageRegistry::builtin()
+    }
+}
+
+/// How much of a file is read when sniffing its content.
+const SNIFF_BYTES: usize = 1024;
+/// How many leading lines are searched for a vim/emacs modeline.
+const MODELINE_LINES: usize = 5;
+
+/// Returns `true` if the line is a `#!` interpreter line (but not a Rust `#![attr]`).
+pub fn is_shebang(line: &str) -> bool {
+    line.starts_with("#!") && !line.starts_with("#![")
+}
+
+/// Extracts the interpreter name from a shebang line.
+///
+/// `#!/usr/bin/env -S python3 -u` gives `python3`; `#!/bin/bash -e` gives `bash`.
+fn shebang_interpreter(line: &str) -> Option<&str> {
+    if !is_shebang(line) {
+        return None;
+    }
+    let mut words = line[2..].split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        words.find(|w| !w.starts_with('-') && !w.contains('='))
+    } else {
+        Some(program)
+    }
+}
+
+/// Extracts a language name from a vim (`vim: set ft=python:`) or emacs (`-*- mode: python -*-`) modeline.
+fn modeline_language(line: &str) -> Option<&str> {
+    if let Some(start) = line.find("-*-") {
+        let rest = &line[start + 3..];
+        let inner = rest[..rest.find("-*-")?].trim();
+        if !inner.contains(':') {
+            return Some(inner).filter(|s| !s.is_empty());
+        }
+        return inner.split(';').find_map(|pair| {
+            let (key, value) = pair.split_once(':')?;
+            key.trim().eq_ignore_ascii_case("mode").then(|| value.trim())
+        });
+    }
+
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.find(marker)
+            .filter(|&idx| idx == 0 || line[..idx].ends_with(char::is_whitespace))
+            .map(|idx| idx + marker.len())
+    })?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|option| option.strip_prefix("ft=").or_else(|| option.strip_prefix("filetype=")))
+        .filter(|s| !s.is_empty())
+}
+
+/// The built-in language table.
+fn builtin_languages() -> Vec<Language> {
+    const C_STRINGS: &[&str] = &["\"", "'"];
+    const C_DOCS: &[&str] = &["///", "/**"];
+    // An unquoted `url(http://...)` holds `//`, which isn't a comment in SCSS or Less.
+    const CSS_RAW_TOKENS: &[(&str, &str)] = &[("url(", ")")];
+    // Comments that linters, formatters, compilers and editors act on.
+    const JS_DIRECTIVES: &[&str] = &[
+        r"^eslint-", r"^@ts-(ignore|expect-error|nocheck|check)\b", r"^prettier-ignore", r"^biome-ignore",
+        r"^(istanbul|c8|v8) ignore", r"^#(end)?region\b", r"^/\s*<(reference|amd-module)\b", r"^webpack[A-Z]",
+        r"^@vite-ignore", r"^(global|jshint|jslint)\s", r"^tslint:",
+    ];
+    const C_DIRECTIVES: &[&str] =
+        &[r"^NOLINT", r"^clang-format (off|on)", r"^IWYU pragma:", r"^cppcheck-suppress", r"(?i)^fall[ -]?through"];
+    const JVM_DIRECTIVES: &[&str] =
+        &[r"^noinspection\b", r"^(end)?region\b", r"^@formatter:(off|on)", r"^NOSONAR", r"^CHECKSTYLE[:.]", r"^ktlint-disable"];
+    const CSS_DIRECTIVES: &[&str] = &[r"^stylelint-", r"^prettier-ignore", r"^!"];
+    const MARKUP_DIRECTIVES: &[&str] = &[r"^prettier-ignore", r"^(svelte-ignore|eslint-)", r"^\[if\s", r"^markdownlint-"];
+
+    vec![
+        // Web & JS
+        Language::new("typescript", &["ts", "tsx"])
+            .interpreters(&["deno", "ts-node", "tsx"]).aliases(&["ts"])
+            .line("//").block("/*", "*/").strings(&["\"", "'", "`"]).docs(&["/**"]).directives(JS_DIRECTIVES)
+            .preamble(&[Preamble::Directives])
+            .preset(ProjectType::Web),
+        Language::new("javascript", &["js", "jsx"])
+            .interpreters(&["node", "nodejs", "bun"]).aliases(&["js"])
+            .line("//").block("/*", "*/").strings(&["\"", "'", "`"]).docs(&["/**"]).directives(JS_DIRECTIVES)
+            .preamble(&[Preamble::Directives])
+            .preset(ProjectType::Web),
+        // Markup gets no strings: its text is full of apostrophes, and comments can't go inside tags.
+        Language::new("svelte", &["svelte"]).block("<!--", "-->").directives(MARKUP_DIRECTIVES).preset(ProjectType::Web),
+        Language::new("vue", &["vue"]).block("<!--", "-->").directives(MARKUP_DIRECTIVES).preset(ProjectType::Web),
+        Language::new("html", &["html"])
+            .block("<!--", "-->").directives(MARKUP_DIRECTIVES).preamble(&[Preamble::XmlDeclaration])
+            .preset(ProjectType::Web),
+        Language::new("css", &["css"])
+            .block("/*", "*/").strings(C_STRINGS).raw_tokens(CSS_RAW_TOKENS).directives(CSS_DIRECTIVES)
+            .preset(ProjectType::Web),
+        Language::new("scss", &["scss"])
+            .line("//").block("/*", "*/").strings(C_STRINGS).raw_tokens(CSS_RAW_TOKENS).directives(CSS_DIRECTIVES).block_header()
+            .preset(ProjectType::Web),
+        Language::new("less", &["less"])
+            .line("//").block("/*", "*/").strings(C_STRINGS).raw_tokens(CSS_RAW_TOKENS).directives(CSS_DIRECTIVES).block_header()
+            .preset(ProjectType::Web),
+        // C-style languages
+        Language::new("c", &["c", "h"]).line("//").block("/*", "*/").strings(C_STRINGS).docs(C_DOCS).directives(C_DIRECTIVES),
+        Language::new("cpp", &["cpp", "hpp"])
+            .aliases(&["c++"]).line("//").block("/*", "*/").strings(C_STRINGS).docs(C_DOCS).directives(C_DIRECTIVES),
+        Language::new("csharp", &["cs"])
+            .line("//").block("/*", "*/").strings(C_STRINGS).docs(C_DOCS)
+            .directives(&[r"^ReSharper\s", r"^<auto-generated", r"^NOLINT"]),
+        Language::new("go", &["go"])
+            .line("//").block("/*", "*/").strings(&["\"", "'", "`"]).raw_strings(&["`"])
+            .directives(&[r"^go:", r"^nolint\b", r"^export\s", r"^\+build\s", r"^line\s"])
+            .preamble(&[Preamble::GoBuild]).header_blank_line(),
+        Language::new("java", &["java"])
+            .line("//").block("/*", "*/").strings(C_STRINGS).docs(&["/**"]).directives(JVM_DIRECTIVES)
+            .preset(ProjectType::Java),
+        Language::new("rust", &["rs"])
+            .line("//").block("/*", "*/").nested().strings(&["\""]).docs(&["///", "//!", "/**", "/*!"])
+            .directives(&[r"^SAFETY:"])
+            .preset(ProjectType::Rust),
+        Language::new("swift", &["swift"])
+            .line("//").block("/*", "*/").nested().strings(&["\"\"\"", "\""]).docs(&["///", "/**"])
+            .directives(&[r"^swiftlint:", r"^MARK:", r"^swiftformat:", r"^sourcery:"]),
+        Language::new("kotlin", &["kt"])
+            .line("//").block("/*", "*/").nested().strings(&["\"\"\"", "\"", "'"]).docs(&["/**"]).directives(JVM_DIRECTIVES),
+        Language::new("typst", &["typ"]).line("//").block("/*", "*/").strings(&["\""]),
+        Language::new("dart", &["dart"])
+            .line("//").block("/*", "*/").nested().strings(&["\"\"\"", "'''", "\"", "'"]).docs(&["///", "/**"])
+            .directives(&[r"^ignore(_for_file)?:", r"^coverage:ignore", r"^@dart\s*="])
+            .preset(ProjectType::Flutter),
+        // Functional languages
+        Language::new("haskell", &["hs"])
+            .interpreters(&["runghc", "runhaskell", "stack"]).aliases(&["hs"])
+            .line("--").block("{-", "-}").nested().strings(&["\""]).docs(&["-- |", "-- ^", "{-|"])
+            // `{-# LANGUAGE ... #-}` pragmas.
+            .directives(&[r"^#"]),
+        Language::new("ocaml", &["ml", "mli"]).block("(*", "*)").nested().strings(&["\""]).docs(&["(**"]),
+        // Scripting languages
+        Language::new("python", &["py"])
+            .interpreters(&["python", "pypy"]).aliases(&["py"])
+            .line("#").strings(&["\"\"\"", "'''", "\"", "'"])
+            .directives(&[
+                r"^type:", r"^noqa\b", r"^pragma\b", r"^(pylint|mypy|pyright|ruff|isort):", r"^fmt:\s*(off|on|skip)",
+                r"^nosec\b", r"^(end)?region\b",
+            ])
+            .preamble(&[Preamble::EncodingLine])
+            .preset(ProjectType::Python),
+        Language::new("ruby", &["rb"])
+            .interpreters(&["ruby"]).aliases(&["rb"])
+            .line("#").strings(C_STRINGS)
+            .directives(&[
+                r"^(frozen_string_literal|encoding|coding|warn_indent|shareable_constant_value|typed):", r"^rubocop:", r"^:nocov:",
+            ])
+            .preamble(&[Preamble::EncodingLine]),
+        Language::new("shell", &["sh", "bash", "zsh"])
+            .filenames(&[".bashrc", ".bash_profile", ".bash_aliases", ".zshrc", ".zprofile", ".profile"])
+            .interpreters(&["sh", "bash", "zsh", "dash", "ksh"]).aliases(&["sh", "bash", "zsh"])
+            .line("#").strings(C_STRINGS).directives(&[r"^shellcheck\s"]),
+        Language::new("perl", &["pl"])
+            .interpreters(&["perl"]).aliases(&["pl"])
+            .line("#").strings(C_STRINGS).directives(&[r"^#?\s*(no|use) critic"]),
+        Language::new("php", &["php"])
+            .interpreters(&["php"])
+            .line("//").block("/*", "*/").strings(C_STRINGS).docs(&["/**"])
+            .directives(&[r"^phpcs:", r"^@(phpstan|psalm)-", r"^@codingStandardsIgnore", r"^@?noinspection\b"])
+            .preamble(&[Preamble::PhpOpenTag]),
+        Language::new("powershell", &["ps1"])
+            .interpreters(&["pwsh", "powershell"]).aliases(&["ps1"])
+            .line("#").strings(C_STRINGS).directives(&[r"(?i)^requires\s+-", r"(?i)^(end)?region\b"]),
+        // Markup & Config
+        Language::new("markdown", &["md"]).block("<!--", "-->").directives(MARKUP_DIRECTIVES).preamble(&[Preamble::FrontMatter]),
+        Language::new("xml", &["xml"])
+            .block("<!--", "-->").directives(MARKUP_DIRECTIVES).preamble(&[Preamble::XmlDeclaration])
+            .preset(ProjectType::Java),
+        Language::new("yaml", &["yaml", "yml"]).line("#").strings(C_STRINGS).directives(&[r"^yamllint\s", r"^yaml-language-server:"]),
+        Language::new("toml", &["toml"]).line("#").strings(C_STRINGS).directives(&[r"^:schema\s", r"^taplo:"]),
+        Language::new("dockerfile", &["dockerfile"])
+            .filenames(&["Dockerfile", "Containerfile"]).patterns(&["Dockerfile.*"])
+            .line("#").strings(C_STRINGS).directives(&[r"^(syntax|escape|check)="]),
+        // Build files
+        Language::new("make", &["mk", "mak"])
+            .filenames(&["Makefile", "makefile", "GNUmakefile"])
+            .interpreters(&["make"]).aliases(&["makefile"])
+            .line("#").strings(C_STRINGS),
+        Language::new("cmake", &["cmake"]).filenames(&["CMakeLists.txt"]).line("#").block("#[[", "]]").strings(&["\""]),
+        Language::new("groovy", &["groovy", "gradle"])
+            .filenames(&["Jenkinsfile"])
+            .line("//").block("/*", "*/").strings(&["\"\"\"", "'''", "\"", "'"]).docs(&["/**"]).directives(JVM_DIRECTIVES),
+        Language::new("dotenv", &["env"]).filenames(&[".env"]).patterns(&[".env.*"]).line("#").strings(C_STRINGS),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_delimiters() {
+        let registry = LanguageRegistry::builtin();
+        assert_eq!(registry.language_for(Path::new("a.rs")).header_delimiters(), ("//", ""));
+        assert_eq!(registry.language_for(Path::new("a.py")).header_delimiters(), ("#", ""));
+        assert_eq!(registry.language_for(Path::new("a.css")).header_delimiters(), ("/*", "*/"));
+        assert_eq!(registry.language_for(Path::new("a.scss")).header_delimiters(), ("/*", "*/"));
+        assert_eq!(registry.language_for(Path::new("a.html")).header_delimiters(), ("<!--", "-->"));
+        assert_eq!(registry.language_for(Path::new("a.unknown")).header_delimiters(), ("//", ""));
+    }
+
+    #[test]
+    fn test_scss_has_line_comments() {
+        let registry = LanguageRegistry::builtin();
+        let scss = registry.detect(Path::new("style.scss")).unwrap();
+        assert_eq!(scss.line_comment.as_deref(), Some("//"));
+    }
+
+    #[test]
+    fn test_user_languages_are_merged() -> Result<()> {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+            [languages.sql]
+            extensions = ["sql"]
+            line_comment = "--"
+            block_comment = ["/*", "*/"]
+
+            [languages.scss]
+            header_style = "line"
+            "#,
+        )?;
+        let registry = LanguageRegistry::with_user_languages(&config.languages)?;
+
+        let sql = registry.language_for(Path::new("schema.sql"));
+        assert_eq!(sql.name, "sql");
+        assert_eq!(sql.header_delimiters(), ("--", ""));
+        assert!(registry.all_extensions().contains(&"sql".to_string()));
+
+        // Overriding a built-in keeps the fields that were not set.
+        let scss = registry.language_for(Path::new("a.scss"));
+        assert_eq!(scss.header_delimiters(), ("//", ""));
+        assert_eq!(scss.block_comment, Some(("/*".to_string(), "*/".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_language_without_comments_is_rejected() -> Result<()> {
+        let config: crate::config::Config = toml::from_str("[languages.nix]\nextensions = [\"nix\"]\n")?;
+        assert!(LanguageRegistry::with_user_languages(&config.languages).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_by_file_name() {
+        let registry = LanguageRegistry::builtin();
+        let name_of = |path: &str| registry.detect(Path::new(path)).map(|l| l.name.as_str());
+        assert_eq!(name_of("Dockerfile"), Some("dockerfile"));
+        assert_eq!(name_of("docker/Dockerfile.prod"), Some("dockerfile"));
+        assert_eq!(name_of("Makefile"), Some("make"));
+        assert_eq!(name_of("ci/Jenkinsfile"), Some("groovy"));
+        assert_eq!(name_of("home/.bashrc"), Some("shell"));
+        assert_eq!(name_of(".env.local"), Some("dotenv"));
+        assert_eq!(name_of("CMakeLists.txt"), Some("cmake"));
+        assert_eq!(name_of("notes.txt"), None);
+        assert_eq!(name_of("README"), None);
+    }
+
+    #[test]
+    fn test_detect_content_from_shebang() {
+        let registry = LanguageRegistry::builtin();
+        let name_of = |head: &str| registry.detect_content(head).map(|l| l.name.as_str());
+        assert_eq!(name_of("#!/usr/bin/env python3\nprint(1)"), Some("python"));
+        assert_eq!(name_of("#!/usr/bin/python3.11 -u\n"), Some("python"));
+        assert_eq!(name_of("#!/bin/bash -e\n"), Some("shell"));
+        assert_eq!(name_of("#!/usr/bin/env -S node --experimental\n"), Some("javascript"));
+        assert_eq!(name_of("#![allow(unused)]\n"), None);
+        assert_eq!(name_of("plain text\n"), None);
+    }
+
+    #[test]
+    fn test_detect_content_from_modeline() {
+        let registry = LanguageRegistry::builtin();
+        let name_of = |head: &str| registry.detect_content(head).map(|l| l.name.as_str());
+        assert_eq!(name_of("# vim: set ft=ruby:\nputs 1"), Some("ruby"));
+        assert_eq!(name_of("x\n# vim: filetype=sh\n"), Some("shell"));
+        assert_eq!(name_of("# -*- mode: python; coding: utf-8 -*-\n"), Some("python"));
+        assert_eq!(name_of("// -*- C++ -*-\n"), Some("cpp"));
+        assert_eq!(name_of("# -*- perl -*-\n"), Some("perl"));
+    }
+
+    #[test]
+    fn test_less_is_supported_everywhere() {
+        let registry = LanguageRegistry::builtin();
+        assert!(registry.all_extensions().contains(&"less".to_string()));
+        assert!(registry.preset_extensions(&ProjectType::Web).contains(&"less".to_string()));
+    }
+}
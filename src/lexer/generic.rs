@@ -0,0 +1,173 @@
+// src/lexer/generic.rs
+
+use super::{Comment, CommentKind, Lexed, Unterminated};
+use crate::languages::Language;
+
+/// Lexes `source` using only the delimiters in the language definition: its line comment,
+/// block comment (nested if the language allows it), string delimiters and raw tokens.
+///
+/// Single-character strings end at the end of the line, except backtick strings (JavaScript
+/// template literals, Go raw strings) which, like multi-character ones such as `"""`, may span lines.
+pub fn lex(language: &Language, source: &str) -> Result<Lexed, Unterminated> {
+    let line = language.line_comment.as_deref().filter(|prefix| !prefix.is_empty());
+    let block = language.block_comment.as_ref().map(|(start, end)| (start.as_str(), end.as_str()));
+    let mut lexed = Lexed::default();
+    let mut i = 0;
+
+    while i < source.len() {
+        let rest = &source[i..];
+        // Block comments go first, as their start may begin with the line prefix (e.g. CMake's `#[[`).
+        if let Some((start, end)) = block.filter(|(start, _)| rest.starts_with(start)) {
+            let close = block_comment_end(source, i, start, end, language.nested_comments)
+                .ok_or_else(|| Unterminated::new("block comment", source, i))?;
+            let doc = is_doc(language, &source[i..close], &format!("{}{}", start, end));
+            lexed.comments.push(Comment { range: i..close, kind: CommentKind::Block, doc });
+            i = close;
+        } else if let Some(close) = raw_token_end(language, source, i) {
+            lexed.strings.push(i..close);
+            i = close;
+        } else if let Some(prefix) = line.filter(|prefix| rest.starts_with(prefix)) {
+            let close = rest.find('\n').map_or(source.len(), |n| i + n);
+            let doc = is_doc(language, &source[i..close], prefix);
+            lexed.comments.push(Comment { range: i..close, kind: CommentKind::Line, doc });
+            i = close;
+        } else if let Some(delimiter) = language.string_delimiters.iter().find(|d| !d.is_empty() && rest.starts_with(d.as_str())) {
+            let escapes = !language.raw_strings.contains(delimiter);
+            let close = string_end(source, i + delimiter.len(), delimiter, escapes)
+                .ok_or_else(|| Unterminated::new("string", source, i))?;
+            lexed.strings.push(i..close);
+            i = close;
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    Ok(lexed)
+}
+
+/// Whether a comment starts with one of the language's doc markers.
+/// A comment that is nothing but the marker's delimiters, such as `/**/`, isn't documentation.
+fn is_doc(language: &Language, comment: &str, empty: &str) -> bool {
+    comment.trim_end() != empty && language.doc_comments.iter().any(|marker| comment.starts_with(marker.as_str()))
+}
+
+/// The end of the block comment starting at `from`, if it is closed.
+fn block_comment_end(source: &str, from: usize, start: &str, end: &str, nested: bool) -> Option<usize> {
+    let mut depth = 1;
+    let mut i = from + start.len();
+    while i < source.len() {
+        let rest = &source[i..];
+        if rest.starts_with(end) {
+            depth -= 1;
+            i += end.len();
+            if depth == 0 {
+                return Some(i);
+            }
+        } else if nested && rest.starts_with(start) {
+            depth += 1;
+            i += start.len();
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    None
+}
+
+/// The end of the unquoted raw token (e.g. `url(...)`) starting at `from`, if one does. A quoted
+/// argument is left to the string rules. An unclosed token ends with its line.
+fn raw_token_end(language: &Language, source: &str, from: usize) -> Option<usize> {
+    let rest = &source[from..];
+    let (start, end) = language.raw_tokens.iter().find(|(start, _)| !start.is_empty() && rest.starts_with(start.as_str()))?;
+    let content = &rest[start.len()..];
+    if language.string_delimiters.iter().any(|d| !d.is_empty() && content.trim_start().starts_with(d.as_str())) {
+        return None;
+    }
+    let line_end = content.find('\n').unwrap_or(content.len());
+    let close = content[..line_end].find(end.as_str()).map_or(line_end, |n| n + end.len());
+    Some(from + start.len() + close)
+}
+
+/// The end of a string whose content starts at `from`. Unless `escapes` is off (raw strings),
+/// backslashes escape the next character. Returns `None` if a string that may span lines is never closed.
+fn string_end(source: &str, from: usize, delimiter: &str, escapes: bool) -> Option<usize> {
+    let spans_lines = delimiter.chars().count() > 1 || delimiter == "`";
+    let mut chars = source[from..].char_indices();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '\\' if escapes => {
+                chars.next();
+            }
+            '\n' if !spans_lines => return Some(from + offset),
+            _ if source[from + offset..].starts_with(delimiter) => return Some(from + offset + delimiter.len()),
+            _ => {}
+        }
+    }
+    if spans_lines { None } else { Some(source.len()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageRegistry;
+    use std::path::Path;
+
+    /// The text of each comment in a file named `name`, with `*` marking doc comments.
+    fn found(name: &str, source: &str) -> Vec<String> {
+        let registry = LanguageRegistry::builtin();
+        lex(registry.language_for(Path::new(name)), source)
+            .unwrap()
+            .comments
+            .into_iter()
+            .map(|c| format!("{}{}", if c.doc { "*" } else { "" }, &source[c.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_strings_hide_comment_markers() {
+        assert_eq!(found("a.py", "s = '# no' # yes\n"), ["# yes"]);
+        assert_eq!(found("a.py", "s = \"\"\"\n# no ''' \n\"\"\" # yes\n"), ["# yes"]);
+        assert_eq!(found("a.js", "s = `\n// no\n` /* yes */\n"), ["/* yes */"]);
+        // Go raw strings have no escapes, so a trailing backslash doesn't swallow the closing backtick.
+        assert_eq!(found("a.go", "var p = `C:\\` // yes\nvar q = \"\\\"\" // yes\n"), ["// yes", "// yes"]);
+        assert_eq!(found("a.css", "a { content: \"/* no */\"; } /* yes */"), ["/* yes */"]);
+        assert_eq!(found("a.scss", ".a { background: url(http://x.io/a.png); } // yes"), ["// yes"]);
+        assert_eq!(found("a.less", ".a { background: url( \"//x.io/a.png\" ); } // yes"), ["// yes"]);
+        // An unclosed quote only hides the rest of its own line.
+        assert_eq!(found("a.sh", "echo it's # no\n# yes\n"), ["# yes"]);
+        // Markup has no strings outside of tags, and apostrophes are everywhere in text.
+        assert_eq!(found("a.html", "<p>Don't</p> <!-- yes -->"), ["<!-- yes -->"]);
+    }
+
+    #[test]
+    fn test_block_comments_and_nesting() {
+        assert_eq!(found("a.swift", "/* a /* b */ c */ x /* d */"), ["/* a /* b */ c */", "/* d */"]);
+        assert_eq!(found("a.c", "/* a /* b */ c */"), ["/* a /* b */"]);
+        assert_eq!(found("a.hs", "x = 1 {- a {- b -} c -} -- d"), ["{- a {- b -} c -}", "-- d"]);
+        assert_eq!(found("CMakeLists.txt", "#[[ block ]] set(x) # line"), ["#[[ block ]]", "# line"]);
+        let registry = LanguageRegistry::builtin();
+        let error = lex(registry.language_for(Path::new("a.ml")), "x\n(* a (* b *)").unwrap_err();
+        assert_eq!(error.to_string(), "block comment opened on line 2 is never closed");
+    }
+
+    #[test]
+    fn test_comment_bodies() {
+        let registry = LanguageRegistry::builtin();
+        let body = |name: &str, source: &str| -> String {
+            let language = registry.language_for(Path::new(name));
+            let lexed = lex(language, source).unwrap();
+            lexed.comments[0].body(source, language).to_string()
+        };
+        assert_eq!(body("a.ts", "x; //  eslint-disable-line "), "eslint-disable-line");
+        assert_eq!(body("a.php", "/** @phpstan-ignore-next-line */"), "@phpstan-ignore-next-line");
+        assert_eq!(body("a.hs", "{-# LANGUAGE GADTs #-}"), "# LANGUAGE GADTs #");
+        assert_eq!(body("a.html", "<!--[if IE]><p>Old</p><![endif]-->"), "[if IE]><p>Old</p><![endif]");
+        assert_eq!(body("a.dart", "/// Docs."), "Docs.");
+        assert_eq!(body("a.hs", "-- | Docs."), "Docs.");
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        assert_eq!(found("a.java", "/** doc */ /* not */ /**/ // not"), ["*/** doc */", "/* not */", "/**/", "// not"]);
+        assert_eq!(found("a.dart", "/// doc\n// not\n"), ["*/// doc", "// not"]);
+        assert_eq!(found("a.hs", "-- | doc\n-- not\n{-| doc -}"), ["*-- | doc", "-- not", "*{-| doc -}"]);
+    }
+}
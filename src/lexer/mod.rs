@@ -0,0 +1,116 @@
+// src/lexer/mod.rs
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::languages::Language;
+
+mod generic;
+mod python;
+mod rust;
+
+/// Whether a comment runs to the end of its line or is delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    Line,
+    Block,
+}
+
+/// A comment found by a lexer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The byte range of the comment, delimiters included. Line comments stop before the newline.
+    pub range: Range<usize>,
+    pub kind: CommentKind,
+    /// Whether this is a documentation comment (e.g. `///` or `/*!` in Rust).
+    pub doc: bool,
+}
+
+impl Comment {
+    /// The comment's text without its delimiters, e.g. `eslint-disable-next-line` for
+    /// `// eslint-disable-next-line`. Doc markers (`///`, `//!`, `-- |`) and the leading `*`s
+    /// of `/**`-style comments go too.
+    pub fn body<'a>(&self, source: &'a str, language: &Language) -> &'a str {
+        let text = &source[self.range.clone()];
+        let doc_marker = language
+            .doc_comments
+            .iter()
+            .filter(|marker| self.doc && text.starts_with(marker.as_str()))
+            .max_by_key(|marker| marker.len());
+        let strip_opening = |text: &'a str, delimiter: &str| -> &'a str {
+            text.strip_prefix(doc_marker.map_or(delimiter, |marker| marker.as_str())).unwrap_or(text)
+        };
+        let inner = match (self.kind, &language.line_comment, &language.block_comment) {
+            (CommentKind::Line, Some(prefix), _) => strip_opening(text, prefix),
+            (CommentKind::Block, _, Some((start, end))) => {
+                let text = strip_opening(text, start);
+                text.strip_suffix(end.as_str()).unwrap_or(text)
+            }
+            _ => text,
+        };
+        inner.trim().trim_start_matches('*').trim_start()
+    }
+
+    /// The 1-based line the comment starts on.
+    pub fn line(&self, source: &str) -> usize {
+        source[..self.range.start].matches('\n').count() + 1
+    }
+}
+
+/// A Python docstring: a string that is the first statement of a module, class or function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Docstring {
+    /// The byte range of the string, quotes included.
+    pub range: Range<usize>,
+    /// Whether it is the only statement of its class or function, whose body can't be left empty.
+    pub sole_statement: bool,
+}
+
+/// The comments and strings of a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lexed {
+    pub comments: Vec<Comment>,
+    /// The byte ranges of string literals, delimiters included.
+    pub strings: Vec<Range<usize>>,
+    /// Docstrings, which are also in `strings`. Only found for Python.
+    pub docstrings: Vec<Docstring>,
+}
+
+impl Lexed {
+    /// Whether the byte at `pos` is inside a string, e.g. a newline in a multi-line string.
+    pub fn in_string(&self, pos: usize) -> bool {
+        let index = self.strings.partition_point(|string| string.end <= pos);
+        self.strings.get(index).is_some_and(|string| string.start < pos)
+    }
+}
+
+/// A comment or string still open at the end of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unterminated {
+    /// What was left open, e.g. "block comment".
+    pub what: &'static str,
+    /// The 1-based line it was opened on.
+    pub line: usize,
+}
+
+impl Unterminated {
+    pub fn new(what: &'static str, source: &str, start: usize) -> Self {
+        Unterminated { what, line: source[..start].matches('\n').count() + 1 }
+    }
+}
+
+impl fmt::Display for Unterminated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} opened on line {} is never closed", self.what, self.line)
+    }
+}
+
+/// Finds the comments and strings in `source`. Rust and Python have their own lexers; every
+/// other language is lexed from the comment and string delimiters in its definition.
+pub fn lex(language: &Language, source: &str) -> Result<Lexed, Unterminated> {
+    match language.name.as_str() {
+        "rust" => rust::lex(source),
+        "python" => python::lex(source),
+        _ => generic::lex(language, source),
+    }
+}
@@ -0,0 +1,291 @@
+// src/lexer/python.rs
+
+use std::ops::Range;
+
+use super::{Comment, CommentKind, Docstring, Lexed, Unterminated};
+
+/// The tokens that matter for telling docstrings apart from other strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// A string literal. f-strings can't be docstrings.
+    Str { range: Range<usize>, fstring: bool },
+    Word(Range<usize>),
+    /// A `:` outside of brackets, which ends a `def` or `class` header.
+    Colon,
+    Other,
+}
+
+/// Finds the comments, strings and docstrings in Python source.
+///
+/// Strings may have any prefix (`r`, `b`, `f`, `rb`, ...). Replacement fields of f-strings are
+/// lexed as code, so they can hold strings with the same quotes (`f"{d["key"]}"`).
+pub fn lex(source: &str) -> Result<Lexed, Unterminated> {
+    let bytes = source.as_bytes();
+    let mut lexed = Lexed::default();
+    // Tokens grouped into logical lines: newlines inside brackets or after `\` don't end one.
+    let mut lines: Vec<Vec<(usize, Token)>> = vec![Vec::new()];
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let token = match bytes[i] {
+            b'#' => {
+                let end = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                lexed.comments.push(Comment { range: i..end, kind: CommentKind::Line, doc: false });
+                i = end;
+                continue;
+            }
+            b'\\' if bytes[i + 1..].starts_with(b"\n") || bytes[i + 1..].starts_with(b"\r\n") => {
+                i += 2;
+                continue;
+            }
+            b'\n' => {
+                if depth == 0 && !lines.last().is_some_and(Vec::is_empty) {
+                    lines.push(Vec::new());
+                }
+                i += 1;
+                continue;
+            }
+            b' ' | b'\t' | b'\r' | b'\x0c' => {
+                i += 1;
+                continue;
+            }
+            b'"' | b'\'' => {
+                i = string_end(bytes, i, false).ok_or_else(|| Unterminated::new("string", source, start))?;
+                Token::Str { range: start..i, fstring: false }
+            }
+            b if is_ident_byte(b) => {
+                while i < bytes.len() && is_ident_byte(bytes[i]) {
+                    i += 1;
+                }
+                match string_prefix(&source[start..i]).filter(|_| matches!(bytes.get(i), Some(b'"' | b'\''))) {
+                    Some(fstring) => {
+                        i = string_end(bytes, i, fstring).ok_or_else(|| Unterminated::new("string", source, start))?;
+                        Token::Str { range: start..i, fstring }
+                    }
+                    None => Token::Word(start..i),
+                }
+            }
+            b'(' | b'[' | b'{' => {
+                depth += 1;
+                i += 1;
+                Token::Other
+            }
+            b')' | b']' | b'}' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+                Token::Other
+            }
+            b':' if depth == 0 && bytes.get(i + 1) != Some(&b'=') => {
+                i += 1;
+                Token::Colon
+            }
+            _ => {
+                i += 1;
+                Token::Other
+            }
+        };
+        if let Token::Str { range, .. } = &token {
+            lexed.strings.push(range.clone());
+        }
+        lines.last_mut().expect("there is always a current line").push((start, token));
+    }
+
+    lines.retain(|line| !line.is_empty());
+    lexed.docstrings = docstrings(source, &lines);
+    Ok(lexed)
+}
+
+/// Whether `word` is a string prefix such as `rb` or `F`, and if so whether it makes an f-string.
+fn string_prefix(word: &str) -> Option<bool> {
+    let lower = word.to_ascii_lowercase();
+    match lower.as_str() {
+        "r" | "u" | "b" | "br" | "rb" => Some(false),
+        "f" | "fr" | "rf" | "t" | "tr" | "rt" => Some(true),
+        _ => None,
+    }
+}
+
+/// The end of the string whose opening quote is at `start`, or `None` if it is never closed.
+/// A single-quoted string that runs into the end of its line ends there.
+fn string_end(bytes: &[u8], start: usize, fstring: bool) -> Option<usize> {
+    let quote = bytes[start];
+    let triple = bytes[start..].starts_with(&[quote; 3]);
+    let mut i = start + if triple { 3 } else { 1 };
+    while i < bytes.len() {
+        match bytes[i] {
+            // Even in raw strings a backslash stops the next quote from closing the string.
+            b'\\' => i += 2,
+            b'\n' if !triple => return Some(i),
+            b if b == quote && (!triple || bytes[i..].starts_with(&[quote; 3])) => {
+                return Some(i + if triple { 3 } else { 1 });
+            }
+            b'{' if fstring && bytes.get(i + 1) == Some(&b'{') => i += 2,
+            b'{' if fstring => i = replacement_field_end(bytes, i)?,
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// The end of the f-string replacement field (`{expr!r:>{width}}`) starting at `start`.
+fn replacement_field_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            // The format spec (`:>10`) is text again, in which only nested fields are code.
+            b':' if depth == 1 && bytes.get(i + 1) != Some(&b'=') => return format_spec_end(bytes, i + 1),
+            b'"' | b'\'' => {
+                // A nested string, possibly with a prefix: skip it whole.
+                let prefix = (start + 1..i).rev().take_while(|&j| bytes[j].is_ascii_alphabetic()).last();
+                let fstring = prefix.is_some_and(|p| bytes[p..i].iter().any(|b| matches!(b, b'f' | b'F' | b't' | b'T')));
+                i = string_end(bytes, i, fstring)?;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The end of a replacement field whose format spec starts at `i`.
+fn format_spec_end(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => i = replacement_field_end(bytes, i)?,
+            b'}' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Finds the docstrings: a string that is the first statement of the module, a class or a function.
+fn docstrings(source: &str, lines: &[Vec<(usize, Token)>]) -> Vec<Docstring> {
+    let mut found = Vec::new();
+    // The module docstring comes before any other statement.
+    if let Some(range) = lines.first().and_then(|line| string_statement(line)) {
+        found.push(Docstring { range, sole_statement: false });
+    }
+
+    for (index, line) in lines.iter().enumerate() {
+        let word = |n: usize| match line.get(n) {
+            Some((_, Token::Word(range))) => &source[range.clone()],
+            _ => "",
+        };
+        let is_header = matches!(word(0), "def" | "class") || (word(0) == "async" && word(1) == "def");
+        let Some(colon) = line.iter().position(|(_, token)| *token == Token::Colon).filter(|_| is_header) else { continue };
+
+        if colon + 1 < line.len() {
+            // `def f(): "Docs."` has its whole body on the header's line.
+            if let Some(range) = string_statement(&line[colon + 1..]) {
+                found.push(Docstring { range, sole_statement: true });
+            }
+            continue;
+        }
+        let indent = indentation(source, line[0].0);
+        let Some(body) = lines.get(index + 1).filter(|body| indentation(source, body[0].0) > indent) else { continue };
+        if let Some(range) = string_statement(body) {
+            let sole_statement = lines.get(index + 2).is_none_or(|next| indentation(source, next[0].0) <= indent);
+            found.push(Docstring { range, sole_statement });
+        }
+    }
+    found.sort_by_key(|docstring| docstring.range.start);
+    found
+}
+
+/// The range of a statement made only of (implicitly concatenated) plain strings.
+fn string_statement(tokens: &[(usize, Token)]) -> Option<Range<usize>> {
+    let ranges: Option<Vec<&Range<usize>>> = tokens
+        .iter()
+        .map(|(_, token)| match token {
+            Token::Str { range, fstring: false } => Some(range),
+            _ => None,
+        })
+        .collect();
+    let ranges = ranges?;
+    Some(ranges.first()?.start..ranges.last()?.end)
+}
+
+/// The width of the indentation of the line holding `pos`.
+fn indentation(source: &str, pos: usize) -> usize {
+    let line_start = source[..pos].rfind('\n').map_or(0, |n| n + 1);
+    pos - line_start
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comments(source: &str) -> Vec<&str> {
+        lex(source).unwrap().comments.into_iter().map(|c| &source[c.range]).collect()
+    }
+
+    fn docstrings(source: &str) -> Vec<(&str, bool)> {
+        lex(source).unwrap().docstrings.into_iter().map(|d| (&source[d.range], d.sole_statement)).collect()
+    }
+
+    #[test]
+    fn test_strings_hide_hashes() {
+        assert_eq!(comments("s = 'a \"\"\" # no' # yes\n"), ["# yes"]);
+        assert_eq!(comments("s = \"\"\"it's # no\n''' # no\n\"\"\" # yes\n"), ["# yes"]);
+        assert_eq!(comments("s = rb'\\' # no' # yes\n"), ["# yes"]);
+        assert_eq!(comments("s = f\"{d[\"k\"]} # no {x:>{w}} {{ # no\" # yes\n"), ["# yes"]);
+        assert_eq!(comments("s = f\"{x:'>10} # no {y!r:\"^5}\" # yes\n"), ["# yes"]);
+        assert_eq!(comments("s = f'''{ {'a': \"#\"}['a'] }''' # yes\n"), ["# yes"]);
+        assert_eq!(comments("if x: # yes\n    pass\n"), ["# yes"]);
+        assert_eq!(lex("x = '''never closed\n").unwrap_err(), Unterminated { what: "string", line: 1 });
+    }
+
+    #[test]
+    fn test_docstrings_are_told_apart_from_strings() {
+        let source = r#"#!/usr/bin/env python
+"""Module docs."""
+import os
+DATA = """not a docstring"""
+
+class A(Base):
+    '''Class docs.'''
+    x = 1
+
+    def f(self, y: int = 2) -> Dict[str, int]:
+        "Function docs." " Continued."
+        """Just a string."""
+        return {}
+
+async def g(): """One-liner."""
+
+def h():
+    """Only statement."""
+    # A comment is not a statement.
+
+def k():
+    f"not a docstring"
+    return 1
+"#;
+        assert_eq!(
+            docstrings(source),
+            [
+                ("\"\"\"Module docs.\"\"\"", false),
+                ("'''Class docs.'''", false),
+                ("\"Function docs.\" \" Continued.\"", false),
+                ("\"\"\"One-liner.\"\"\"", true),
+                ("\"\"\"Only statement.\"\"\"", true),
+            ]
+        );
+    }
+}
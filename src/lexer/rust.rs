@@ -0,0 +1,214 @@
+// src/lexer/rust.rs
+
+use super::{Comment, CommentKind, Lexed, Unterminated};
+
+/// Finds the comments and strings in Rust source. Strings include raw strings, byte and C strings,
+/// and char literals, all of which can contain comment markers without being a comment.
+pub fn lex(source: &str) -> Result<Lexed, Unterminated> {
+    let bytes = source.as_bytes();
+    let mut lexed = Lexed::default();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                let end = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                let text = &source[i..end];
+                // `////` and longer are ordinary comments.
+                let doc = (text.starts_with("///") && !text.starts_with("////")) || text.starts_with("//!");
+                lexed.comments.push(Comment { range: i..end, kind: CommentKind::Line, doc });
+                i = end;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = block_comment_end(bytes, i).ok_or_else(|| Unterminated::new("block comment", source, i))?;
+                let text = &source[i..end];
+                // `/**/` is empty and `/***` is decoration, not documentation.
+                let doc = (text.starts_with("/**") && !text.starts_with("/***") && text != "/**/") || text.starts_with("/*!");
+                lexed.comments.push(Comment { range: i..end, kind: CommentKind::Block, doc });
+                i = end;
+            }
+            b'"' => {
+                let end = string_end(bytes, i + 1).ok_or_else(|| Unterminated::new("string", source, i))?;
+                lexed.strings.push(i..end);
+                i = end;
+            }
+            b'\'' => {
+                let end = quote_end(source, i);
+                // A lifetime is just the quote.
+                if end > i + 1 {
+                    lexed.strings.push(i..end);
+                }
+                i = end;
+            }
+            b'r' | b'b' | b'c' if !is_ident_byte(bytes, i.wrapping_sub(1)) => {
+                let (end, literal) = prefixed_literal_end(source, i).ok_or_else(|| Unterminated::new("string", source, i))?;
+                if literal {
+                    lexed.strings.push(i..end);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    Ok(lexed)
+}
+
+/// The end of the (possibly nested) block comment starting at `start`, if it is closed.
+fn block_comment_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"/*") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// The end of a `"` string whose content starts at `i`, if it is closed.
+fn string_end(bytes: &[u8], mut i: usize) -> Option<usize> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Skips a `'` that starts either a char literal (`'a'`, `'\n'`, `'"'`) or a lifetime or label (`'a`).
+fn quote_end(source: &str, start: usize) -> usize {
+    let bytes = source.as_bytes();
+    match bytes.get(start + 1) {
+        Some(b'\\') => {
+            // An escape such as '\'' or '\u{1F600}': the literal ends at the next unescaped quote.
+            let mut i = start + 3;
+            while i < bytes.len() && bytes[i] != b'\'' && bytes[i] != b'\n' {
+                i += 1;
+            }
+            (i + 1).min(bytes.len())
+        }
+        Some(_) => {
+            // A char literal holds exactly one (possibly multi-byte) char; anything else is a lifetime.
+            let len = source[start + 1..].chars().next().map_or(1, char::len_utf8);
+            if bytes.get(start + 1 + len) == Some(&b'\'') { start + 2 + len } else { start + 1 }
+        }
+        None => start + 1,
+    }
+}
+
+/// Skips a literal with a prefix: `r"..."`, `r#"..."#`, `b"..."`, `br#"..."#`, `b'x'`, `c"..."`, `cr"..."`.
+/// Anything else starting with these letters is an identifier, which is skipped whole.
+/// Returns the end and whether it was a literal, or `None` for a literal that is never closed.
+fn prefixed_literal_end(source: &str, start: usize) -> Option<(usize, bool)> {
+    let bytes = source.as_bytes();
+    let mut i = start;
+    if matches!(bytes[i], b'b' | b'c') {
+        i += 1;
+        match bytes.get(i) {
+            Some(b'"') => return string_end(bytes, i + 1).map(|end| (end, true)),
+            Some(b'\'') if bytes[start] == b'b' => return Some((quote_end(source, i), true)),
+            Some(b'r') => {}
+            _ => return Some((ident_end(bytes, start), false)),
+        }
+    }
+    // A raw string: `r`, any number of `#`, then `"`. It ends at `"` followed by as many `#`.
+    i += 1;
+    let hashes = bytes[i..].iter().take_while(|&&b| b == b'#').count();
+    if bytes.get(i + hashes) != Some(&b'"') {
+        return Some((ident_end(bytes, start), false));
+    }
+    let mut closing = vec![b'"'];
+    closing.extend(std::iter::repeat_n(b'#', hashes));
+    let content = i + hashes + 1;
+    bytes[content..]
+        .windows(closing.len())
+        .position(|window| window == closing.as_slice())
+        .map(|n| (content + n + closing.len(), true))
+}
+
+fn ident_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while is_ident_byte(bytes, i) {
+        i += 1;
+    }
+    i.max(start + 1)
+}
+
+fn is_ident_byte(bytes: &[u8], i: usize) -> bool {
+    bytes.get(i).is_some_and(|&b| b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text of each comment, with `*` marking doc comments.
+    fn found(source: &str) -> Vec<String> {
+        lex(source)
+            .unwrap()
+            .comments
+            .into_iter()
+            .map(|c| format!("{}{}", if c.doc { "*" } else { "" }, &source[c.range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_strings_and_chars_hide_comment_markers() {
+        assert_eq!(found(r#"let s = "// no"; // yes"#), ["// yes"]);
+        assert_eq!(found(r#"let s = "a \" // no"; /* yes */"#), ["/* yes */"]);
+        assert_eq!(found(r##"let s = r#"// no " /* no"#; // yes"##), ["// yes"]);
+        assert_eq!(found(r###"let s = br##"a "# // no"##; // yes"###), ["// yes"]);
+        assert_eq!(found(r#"let s = b"// no"; let t = c"/* no */";"#), Vec::<String>::new());
+        assert_eq!(found(r#"let q = '"'; // yes " no"#), [r#"// yes " no"#]);
+        assert_eq!(found(r"let c = '\''; let d = b'/'; // yes"), ["// yes"]);
+        assert_eq!(found("let e = 'é'; // yes"), ["// yes"]);
+    }
+
+    #[test]
+    fn test_lifetimes_are_not_char_literals() {
+        assert_eq!(found("fn f<'a>(s: &'a str) -> &'a str { s } // yes"), ["// yes"]);
+        assert_eq!(found("'outer: loop { break 'outer; } // yes"), ["// yes"]);
+        assert_eq!(found("impl<'a> X<'a> { /* yes */ }"), ["/* yes */"]);
+    }
+
+    #[test]
+    fn test_identifiers_starting_with_prefixes() {
+        assert_eq!(found(r#"let br = 1; let r#type = "// no"; // yes"#), ["// yes"]);
+        assert_eq!(found(r#"for"#), Vec::<String>::new());
+        assert_eq!(found(r#"bar("// no") // yes"#), ["// yes"]);
+    }
+
+    #[test]
+    fn test_nested_and_doc_comments() {
+        assert_eq!(found("/* a /* b */ c */ x /* d */"), ["/* a /* b */ c */", "/* d */"]);
+        assert_eq!(found("/// doc\n//! inner\n//// not\n// plain"), ["*/// doc", "*//! inner", "//// not", "// plain"]);
+        assert_eq!(found("/** doc */ /*! inner */ /*** not */ /**/"), ["*/** doc */", "*/*! inner */", "/*** not */", "/**/"]);
+    }
+
+    #[test]
+    fn test_strings_are_recorded() {
+        let source = "let a: &'a str = \"x\"; let b = r#\"y\"#; let c = 'z'; // \"no\"";
+        let strings: Vec<&str> = lex(source).unwrap().strings.into_iter().map(|s| &source[s]).collect();
+        assert_eq!(strings, ["\"x\"", "r#\"y\"#", "'z'"]);
+    }
+
+    #[test]
+    fn test_unterminated_comments_and_strings_are_reported() {
+        let error = lex("fn f() {}\n/* a /* b */\nfn g() {}\n").unwrap_err();
+        assert_eq!(error, Unterminated { what: "block comment", line: 2 });
+        assert_eq!(error.to_string(), "block comment opened on line 2 is never closed");
+        assert_eq!(lex("let s = r#\"a\"; // b\n").unwrap_err(), Unterminated { what: "string", line: 1 });
+        assert_eq!(lex("let s = \"a\\\"").unwrap_err(), Unterminated { what: "string", line: 1 });
+    }
+}
@@ -3,5 +3,11 @@
 // Declare all our logic modules here
 pub mod cli;
 pub mod commands;
-pub mod file_utils;
+pub mod config;
+pub mod header;
+pub mod journal;
+pub mod languages;
+pub mod lexer;
+pub mod license;
+pub mod text;
 pub mod updater; 
\ No newline at end of file
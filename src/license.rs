@@ -0,0 +1,257 @@
+// src/license.rs
+
+use anyhow::{bail, Result};
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+use crate::header::{comment_line, today};
+use crate::languages::Language;
+
+/// The copyright line written when no `[license] template` is configured.
+pub const DEFAULT_LICENSE_TEMPLATE: &str = "Copyright (c) {years} {holder}";
+
+const SPDX_TAG: &str = "SPDX-License-Identifier:";
+/// How far past the path header the license block is searched for.
+const SEARCH_LINES: usize = 30;
+
+/// A year, or a list or range of years such as `2019-2023, 2025`.
+static YEARS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b\d{4}(?:\s*(?:-|–|,)\s*\d{4})*\b").unwrap());
+/// The comment decoration at the start of a line, e.g. `// `, ` * ` or `<!-- `.
+static COMMENT_LEAD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[ \t]*[^\w\s]*[ \t]*").unwrap());
+static COPYRIGHT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bcopyright\b|SPDX-FileCopyrightText:|©|\(c\)").unwrap());
+
+/// A license block found near the top of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseBlock {
+    /// The lines of the comment holding the license, including block comment openers and closers.
+    pub lines: Range<usize>,
+    pub spdx_line: Option<usize>,
+    pub copyright_lines: Vec<usize>,
+}
+
+/// What `LicenseSpec::apply` did to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseChange {
+    Added(String),
+    Updated(String),
+    UpToDate,
+}
+
+/// The license every file should carry: an SPDX identifier and a copyright line from a template.
+#[derive(Debug, Clone)]
+pub struct LicenseSpec {
+    spdx: String,
+    holder: String,
+    template: String,
+}
+
+impl LicenseSpec {
+    pub fn new(spdx: &str, holder: &str, template: Option<&str>) -> Result<Self> {
+        let template = template.unwrap_or(DEFAULT_LICENSE_TEMPLATE);
+        if !template.contains("{years}") && !template.contains("{year}") {
+            bail!("License template must contain {{years}} or {{year}}: {:?}", template);
+        }
+        Ok(LicenseSpec { spdx: spdx.to_string(), holder: holder.to_string(), template: template.to_string() })
+    }
+
+    fn copyright_text(&self) -> String {
+        let year = today().0.to_string();
+        self.template.replace("{years}", &year).replace("{year}", &year).replace("{holder}", &self.holder)
+    }
+
+    /// The full license block for a file of `language`, without a trailing newline.
+    pub fn render(&self, language: &Language) -> String {
+        format!(
+            "{}\n{}",
+            comment_line(language, &format!("{} {}", SPDX_TAG, self.spdx)),
+            comment_line(language, &self.copyright_text())
+        )
+    }
+
+    /// Adds the license block at line `start` (normally just below the path header), or brings an
+    /// existing block up to date: the SPDX identifier, and the year range of the holder's copyright line.
+    pub fn apply(&self, content: &str, language: &Language, start: usize) -> LicenseChange {
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+
+        let Some(block) = find_license(content, language, start) else {
+            let at = start.min(lines.len());
+            if at == lines.len() && lines.last().is_some_and(|l| !l.ends_with('\n')) {
+                lines.last_mut().unwrap().push('\n');
+            }
+            lines.insert(at, format!("{}\n", self.render(language)));
+            return LicenseChange::Added(lines.concat());
+        };
+
+        // Edits that insert lines go bottom-up, so the indices in `block` stay valid.
+        if let Some(i) = block.spdx_line {
+            let idx = lines[i].find(SPDX_TAG).unwrap() + SPDX_TAG.len();
+            let rest = &lines[i][idx..];
+            let id = rest.split_whitespace().next().unwrap_or("");
+            // Keep anything after the identifier, like a closing `*/`.
+            let tail = rest.trim_start().strip_prefix(id).unwrap_or("");
+            lines[i] = format!("{} {}{}", &lines[i][..idx], self.spdx, tail);
+        }
+
+        let holder_line = block.copyright_lines.iter().copied().find(|&i| lines[i].contains(&self.holder));
+        match holder_line {
+            Some(i) => lines[i] = update_years(&lines[i], today().0),
+            None => {
+                // Someone else's copyright: ours goes below it, in the same comment style.
+                let after = block.copyright_lines.last().or(block.spdx_line.as_ref()).copied().unwrap_or(block.lines.start);
+                let line = restyle(&lines[after], &self.copyright_text(), language);
+                lines.insert(after + 1, line);
+            }
+        }
+
+        if block.spdx_line.is_none() {
+            let first = block.copyright_lines[0];
+            let line = restyle(&lines[first], &format!("{} {}", SPDX_TAG, self.spdx), language);
+            lines.insert(first, line);
+        }
+
+        let updated = lines.concat();
+        if updated == content { LicenseChange::UpToDate } else { LicenseChange::Updated(updated) }
+    }
+}
+
+/// Finds the comment holding a license, searching the leading comments from line `start` on.
+/// Every line of a `language` block comment counts, even one without a ` * ` prefix.
+pub fn find_license(content: &str, language: &Language, start: usize) -> Option<LicenseBlock> {
+    let lines: Vec<&str> = content.lines().collect();
+    let end = (start + SEARCH_LINES).min(lines.len());
+    // The license is in the first run of comment lines that mentions one; code ends the search.
+    let mut run_start = start;
+    let mut block_end: Option<&str> = None;
+    for i in start..=end {
+        let line = lines.get(i).map_or("", |l| l.trim());
+        if i < end {
+            if let Some(close) = block_end {
+                if line.contains(close) {
+                    block_end = None;
+                }
+                continue;
+            }
+            if let Some((open, close)) = &language.block_comment
+                && let Some(rest) = line.strip_prefix(open.as_str())
+            {
+                if !rest.contains(close.as_str()) {
+                    block_end = Some(close.as_str());
+                }
+                continue;
+            }
+            if is_comment_like(line) {
+                continue;
+            }
+        }
+        let run = run_start..i;
+        let spdx_line = run.clone().find(|&j| lines[j].contains(SPDX_TAG));
+        let copyright_lines: Vec<usize> = run.clone().filter(|&j| COPYRIGHT.is_match(lines[j])).collect();
+        if spdx_line.is_some() || !copyright_lines.is_empty() {
+            return Some(LicenseBlock { lines: run, spdx_line, copyright_lines });
+        }
+        if i < end && !line.is_empty() {
+            return None;
+        }
+        run_start = i + 1;
+    }
+    None
+}
+
+/// A line that looks like part of a comment: it starts with punctuation such as `//`, `#`, `*` or `<!--`.
+fn is_comment_like(line: &str) -> bool {
+    line.chars().next().is_some_and(|c| c.is_ascii_punctuation() && c != '{' && c != '(' && c != '[' && c != '"' && c != '\'')
+}
+
+/// Rewrites the years on a copyright line to run from the earliest one through `current_year`.
+fn update_years(line: &str, current_year: i64) -> String {
+    let Some(years) = YEARS.find(line) else {
+        return line.to_string();
+    };
+    let first = years.as_str()[..4].parse::<i64>().unwrap_or(current_year);
+    let range = if first >= current_year { first.to_string() } else { format!("{}-{}", first, current_year) };
+    format!("{}{}{}", &line[..years.start()], range, &line[years.end()..])
+}
+
+/// Writes `text` as a new line with the same comment decoration as `model` (e.g. ` * ` inside a block).
+/// A model with no decoration is a bare line inside a block comment, so only its indentation is copied.
+fn restyle(model: &str, text: &str, language: &Language) -> String {
+    let lead = COMMENT_LEAD.find(model).map_or("", |m| m.as_str());
+    if lead.trim().is_empty() {
+        return format!("{}{}\n", lead, text);
+    }
+    match &language.block_comment {
+        Some((open, close)) if lead.trim_start().starts_with(open.as_str()) && model.trim_end().ends_with(close.as_str()) => {
+            format!("{}{} {}\n", lead, text, close)
+        }
+        _ => format!("{}{}\n", lead, text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::languages::LanguageRegistry;
+    use std::path::Path;
+
+    fn language(file: &str) -> Language {
+        LanguageRegistry::builtin().language_for(Path::new(file)).clone()
+    }
+
+    #[test]
+    fn test_update_years() {
+        assert_eq!(update_years("// Copyright 2019 Acme\n", 2026), "// Copyright 2019-2026 Acme\n");
+        assert_eq!(update_years("// Copyright (c) 2019-2021, 2023 Acme\n", 2026), "// Copyright (c) 2019-2026 Acme\n");
+        assert_eq!(update_years("# (c) 2026 Acme\n", 2026), "# (c) 2026 Acme\n");
+        assert_eq!(update_years("# Copyright Acme\n", 2026), "# Copyright Acme\n");
+    }
+
+    #[test]
+    fn test_find_license_in_any_comment_style() {
+        let c_block = "/*\n * Copyright (c) 2020 Acme\n * SPDX-License-Identifier: MIT\n */\nint x;\n";
+        assert_eq!(
+            find_license(c_block, &language("a.c"), 0),
+            Some(LicenseBlock { lines: 0..4, spdx_line: Some(2), copyright_lines: vec![1] })
+        );
+        let after_header = "# Path:a.py\n\n# SPDX-License-Identifier: MIT\nimport os\n";
+        assert_eq!(find_license(after_header, &language("a.py"), 1).map(|b| b.lines), Some(2..3));
+        assert_eq!(find_license("# just a note\nimport os\n# Copyright 2020 Acme\n", &language("a.py"), 0), None);
+        // Lines inside a block comment need no decoration of their own.
+        let bare = "/*\n   Copyright 2020 Acme\n   SPDX-License-Identifier: MIT\n*/\nint x;\n";
+        assert_eq!(
+            find_license(bare, &language("a.c"), 0),
+            Some(LicenseBlock { lines: 0..4, spdx_line: Some(2), copyright_lines: vec![1] })
+        );
+    }
+
+    #[test]
+    fn test_apply_keeps_existing_style() {
+        let spec = LicenseSpec::new("Apache-2.0", "Acme", None).unwrap();
+        let year = today().0;
+        let content = "/*\n * Copyright (c) 2020 Acme\n */\nint x;\n";
+        let expected = format!("/*\n * SPDX-License-Identifier: Apache-2.0\n * Copyright (c) 2020-{} Acme\n */\nint x;\n", year);
+        assert_eq!(spec.apply(content, &language("a.c"), 0), LicenseChange::Updated(expected.clone()));
+        assert_eq!(spec.apply(&expected, &language("a.c"), 0), LicenseChange::UpToDate);
+
+        let css = "/* SPDX-License-Identifier: MIT */\n/* Copyright 2021 Other */\na {}\n";
+        let expected = format!(
+            "/* SPDX-License-Identifier: Apache-2.0 */\n/* Copyright 2021 Other */\n/* Copyright (c) {} Acme */\na {{}}\n",
+            year
+        );
+        assert_eq!(spec.apply(css, &language("a.css"), 0), LicenseChange::Updated(expected));
+    }
+
+    #[test]
+    fn test_apply_updates_bare_block_comments() {
+        let spec = LicenseSpec::new("Apache-2.0", "Acme", None).unwrap();
+        let year = today().0;
+        let c = "/*\n   Copyright 2020 Acme\n   SPDX-License-Identifier: MIT\n*/\nint x;\n";
+        let expected = format!("/*\n   Copyright 2020-{} Acme\n   SPDX-License-Identifier: Apache-2.0\n*/\nint x;\n", year);
+        assert_eq!(spec.apply(c, &language("a.c"), 0), LicenseChange::Updated(expected));
+
+        let html = "<!--\n  Copyright 2020 Acme\n-->\n<p>hi</p>\n";
+        let expected = format!("<!--\n  SPDX-License-Identifier: Apache-2.0\n  Copyright 2020-{} Acme\n-->\n<p>hi</p>\n", year);
+        assert_eq!(spec.apply(html, &language("a.html"), 0), LicenseChange::Updated(expected));
+    }
+}
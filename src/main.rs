@@ -1,18 +1,22 @@
 // src/main.rs
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 // -----------------------------------------------------------
 
 use filedress::cli::Cli;
 use filedress::commands::handle_command;
+use filedress::config::apply_config;
 use filedress::updater::check_for_updates; 
 
 fn main() -> Result<()> {
     // 1. Trigger the (non-blocking) update check at the start.
     check_for_updates(); 
-    // 2. Parse the command-line arguments
-    let cli = Cli::parse();
+    // 2. Parse the command-line arguments, then fill in anything not given
+    //    on the command line from the environment and config files.
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    apply_config(&mut cli.command, &matches)?;
 
     // 3. Pass the parsed command to the handler from our library
     handle_command(&cli.command)?;
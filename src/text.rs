@@ -0,0 +1,82 @@
+// src/text.rs
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const BOM: char = '\u{feff}';
+
+/// A text file split into what commands edit and the encoding details they must not disturb.
+///
+/// Commands work on `content`, which has no BOM and uses `\n` line endings; `encode` turns
+/// edited content back into the file's own style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFile {
+    /// The file's content exactly as read.
+    pub raw: String,
+    /// The content without a BOM, with `\r\n` turned into `\n` if every line ends that way.
+    pub content: String,
+    bom: bool,
+    crlf: bool,
+}
+
+impl TextFile {
+    pub fn read(path: &Path) -> io::Result<Self> {
+        fs::read_to_string(path).map(Self::new)
+    }
+
+    pub fn new(raw: String) -> Self {
+        let text = raw.strip_prefix(BOM);
+        let bom = text.is_some();
+        let text = text.unwrap_or(&raw);
+        // Files with mixed line endings are edited as they are, so their `\r`s stay where they were.
+        let crlf = text.contains("\r\n") && text.matches('\n').count() == text.matches("\r\n").count();
+        let content = if crlf { text.replace("\r\n", "\n") } else { text.to_string() };
+        TextFile { raw, content, bom, crlf }
+    }
+
+    /// Turns edited `content` back into file content with this file's BOM, line endings
+    /// and trailing-newline state.
+    pub fn encode(&self, content: &str) -> String {
+        let mut content = content.to_string();
+        // An empty file has no trailing-newline state worth keeping.
+        if !self.content.is_empty() && !content.is_empty() {
+            match (self.content.ends_with('\n'), content.ends_with('\n')) {
+                (true, false) => content.push('\n'),
+                (false, true) => {
+                    content.pop();
+                }
+                _ => {}
+            }
+        }
+        if self.crlf {
+            content = content.replace('\n', "\r\n");
+        }
+        if self.bom {
+            content.insert(0, BOM);
+        }
+        content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_and_keeps_style() {
+        for raw in ["a\nb\n", "a\r\nb\r\n", "\u{feff}a\r\nb", "a\r\nb\n", "", "\u{feff}"] {
+            let file = TextFile::new(raw.to_string());
+            assert_eq!(file.encode(&file.content), raw);
+        }
+
+        let file = TextFile::new("\u{feff}x = 1\r\ny = 2".to_string());
+        assert_eq!(file.content, "x = 1\ny = 2");
+        assert_eq!(file.encode("# Path:a.py\nx = 1\ny = 2"), "\u{feff}# Path:a.py\r\nx = 1\r\ny = 2");
+        assert_eq!(file.encode("x = 1\n"), "\u{feff}x = 1");
+
+        // Mixed endings are left alone; new lines get `\n`.
+        let file = TextFile::new("a\r\nb\n".to_string());
+        assert_eq!(file.encode(&format!("# h\n{}", file.content)), "# h\na\r\nb\n");
+    }
+}
@@ -45,6 +45,7 @@ fn write_config(path: &PathBuf) -> Result<()> {
 }
 
 /// Checks if we should perform an update check based on the last checked time.
+#[allow(clippy::collapsible_if)]
 fn should_check() -> bool {
     if let Some(path) = get_config_path() {
         if let Ok(config) = read_config(&path) {
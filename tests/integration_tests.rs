@@ -5,7 +5,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tempfile::{tempdir, TempDir};
 
-use filedress::cli::{Args, Commands};
+use filedress::cli::{Args, CleanArgs, Commands};
 use filedress::commands::handle_command;
 // No longer needs: use filedress::commands::clean::clean as clean_command_func;
 
@@ -187,6 +187,7 @@ fn test_depth_parameter_deep() -> Result<()> {
 
 
 // --- NEW Clean Test Environment ---
+#[allow(dead_code)]
 struct CleanTestEnv {
     _temp_dir: TempDir,
     root: PathBuf,
@@ -206,6 +207,7 @@ struct CleanTestEnv {
     original_complex_python_content: String,
 }
 
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn setup_clean_test_files() -> Result<CleanTestEnv> {
     let temp_dir = tempdir()?;
     let root = temp_dir.path().join("clean_test_root");
@@ -247,12 +249,13 @@ fn setup_clean_test_files() -> Result<CleanTestEnv> {
 }
 
 // Helper to run the clean command (the main command handler, not the direct function)
+#[allow(clippy::ptr_arg)]
 fn run_clean_command_on_dir(directory: &PathBuf) -> Result<()> {
     let clean_args = Args {
         directory: directory.clone(),
         ..Default::default()
     };
-    handle_command(&Commands::Clean(clean_args))
+    handle_command(&Commands::Clean(CleanArgs { args: clean_args, ..Default::default() }))
 }
 
 // Helper to read file and assert content
@@ -394,6 +397,18 @@ fn do_stuff() {
     s = "bar";
     let url = "http://example.com/foo.rs?param=value";
     let x = 10;
+    let y = 20;
+}
+struct Holder<'a> {
+    name: &'a str,
+}
+impl<'a> Holder<'a> {
+    fn first(&self) -> char {
+        'outer: for c in self.name.chars() {
+            if c == '"' || c == '/' { break 'outer; }
+        }
+        '\''
+    }
 }
 "#.trim();
     assert_file_content(&env.complex_rust_file, expected_content)?;
@@ -407,6 +422,7 @@ fn test_clean_complex_python_file() -> Result<()> {
 
     // Python docstrings (triple quotes) are treated as code and preserved.
     // All other # comments, including inline and full-line, should be removed.
+    // Blank lines that never held a comment stay, whitespace and all.
     let expected_content = r#"
 # Path: clean_test_root/complex_python.py
 def process_data():
@@ -417,7 +433,10 @@ def process_data():
     data = {"key": "value"}
     if "key" in data:
         print(f"Data has key: {data['key']}")
+    
+    
     url = "https://api.example.com/#anchor";
+    
     '''This is a single line docstring, also preserved.'''
 "#.trim();
     assert_file_content(&env.complex_python_file, expected_content)?;
@@ -439,6 +458,10 @@ another_string = 'Another string with // slashes.'
 comment_start_literal = '''# Not a comment, it's a string literal.'''
 code_with_hash = "some_value"
 final_line = "value/#here_in_string"
+triple_in_string = "not a ''' docstring"
+nested = f"{data["key"]} # not a comment"
+multi = """It's a # hash
+in a 'multi-line' string"""
 "#.trim();
     assert_file_content(&env.python_file_with_strings, expected_content)?;
     Ok(())
@@ -449,15 +472,1024 @@ fn test_clean_preserves_comment_markers_in_strings_rust() -> Result<()> {
     let env = setup_clean_test_files()?;
     run_clean_command_on_dir(&env.root)?;
 
-    let expected_content = r##"
+    let expected_content = r###"
 // Path: clean_test_root/string_rust.rs
 fn process() {
     let my_str = "This string contains // slashes.";
     let another_str = "A string with \"quoted\" text and // more slashes.";
     let third_str = r#"Raw string // with comments"#;
+    let fourth_str = r##"Raw with "# quote and /* not a comment */"##;
+    let bytes = b"Bytes // not a comment";
+    let raw_bytes = br#"Raw bytes /* too */"#;
+    let multi_line = "A string that
+    spans lines // still a string";
     let x = 10;
 }
-"##.trim();
+"###.trim();
     assert_file_content(&env.rust_file_with_strings, expected_content)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_clean_tracks_nested_block_comments() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let swift = root.join("a.swift");
+    fs::write(&swift, "let a = 1 /* outer /* inner */ still a comment */\n/* one\n /* two */\n let b = 2\n*/\nlet c = 3\n")?;
+    let haskell = root.join("b.hs");
+    fs::write(&haskell, "main = pure () {- outer {- inner -} still -}\n-- line\nx = 1\n")?;
+    let ocaml = root.join("c.ml");
+    fs::write(&ocaml, "(* outer (* inner *) still *)\nlet x = 1 (* trailing *)\n")?;
+    // CSS comments don't nest, so the first `*/` ends the comment.
+    let css = root.join("d.css");
+    fs::write(&css, "/* a /* b */ a { color: red; }\n")?;
+
+    run_clean_command_on_dir(&root.to_path_buf())?;
+    assert_file_content(&swift, "let a = 1\nlet c = 3")?;
+    assert_file_content(&haskell, "main = pure ()\nx = 1")?;
+    assert_file_content(&ocaml, "let x = 1")?;
+    assert_file_content(&css, "a { color: red; }")?;
+    Ok(())
+}
+
+#[test]
+fn test_clean_handles_go_raw_strings_ending_in_backslash() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(root.join("main.go"), "package main\n\nvar p = `C:\\` // drive\n\nfunc main() {} // entry\n")?;
+
+    run_clean_command_on_dir(&root.to_path_buf())?;
+    assert_file_content(&root.join("main.go"), "package main\n\nvar p = `C:\\`\n\nfunc main() {}")?;
+    Ok(())
+}
+
+#[test]
+fn test_clean_keeps_unquoted_css_urls() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    for name in ["a.scss", "b.less"] {
+        fs::write(root.join(name), ".a { background: url(http://example.com/x.png); } // note\n")?;
+    }
+    fs::write(root.join("c.css"), ".a { background: url(//example.com/x.png); } /* note */\n")?;
+
+    run_clean_command_on_dir(&root.to_path_buf())?;
+    for name in ["a.scss", "b.less"] {
+        assert_file_content(&root.join(name), ".a { background: url(http://example.com/x.png); }")?;
+    }
+    assert_file_content(&root.join("c.css"), ".a { background: url(//example.com/x.png); }")?;
+    Ok(())
+}
+
+#[test]
+fn test_clean_leaves_unterminated_comments_alone() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let unterminated = [
+        ("a.rs", "fn a() {} // note\n/* outer /* inner */\nfn b() {}\n"),
+        ("b.kt", "fun a() {} // note\n/* outer /* inner */\nfun b() {}\n"),
+        ("c.css", "a {} /* never closed\nb {}\n"),
+    ];
+    for (name, content) in unterminated {
+        fs::write(root.join(name), content)?;
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+        .arg("clean")
+        .arg(root)
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("[UNTERMINATED] Not cleaning, block comment opened on line 2 is never closed"), "{}", stdout);
+    assert!(stdout.contains("[UNTERMINATED] Not cleaning, block comment opened on line 1 is never closed"), "{}", stdout);
+    for (name, content) in unterminated {
+        assert_eq!(fs::read_to_string(root.join(name))?, content);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_clean_keep_docs_keeps_only_doc_comments() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let rust = root.join("lib.rs");
+    fs::write(&rust, "//! Crate docs\n/// Adds one.\n// Implementation note\nfn inc(x: i32) -> i32 { x + 1 } // trailing\n/** Block doc */\n/* plain */\nstruct S;\n")?;
+    let java = root.join("A.java");
+    fs::write(&java, "/**\n * Docs.\n */\n// note\nclass A {}\n")?;
+
+    handle_command(&Commands::Clean(CleanArgs {
+        args: Args { directory: root.to_path_buf(), ..Default::default() },
+        keep_docs: true,
+        ..Default::default()
+    }))?;
+    assert_file_content(&rust, "//! Crate docs\n/// Adds one.\nfn inc(x: i32) -> i32 { x + 1 }\n/** Block doc */\nstruct S;")?;
+    assert_file_content(&java, "/**\n * Docs.\n */\nclass A {}")?;
+    Ok(())
+}
+
+#[test]
+fn test_clean_strip_docstrings() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let file = root.join("mod.py");
+    fs::write(&file, r#""""Module docs."""
+TEMPLATE = """Data, # not a docstring."""
+
+class Empty:
+    """Only a docstring.
+
+    Over two lines.
+    """
+
+def f(x):  # comment
+    '''Docs.'''
+    return x
+
+def g(): "One-liner."
+"#)?;
+
+    handle_command(&Commands::Clean(CleanArgs {
+        args: Args { directory: root.to_path_buf(), ..Default::default() },
+        strip_docstrings: true,
+        ..Default::default()
+    }))?;
+    assert_file_content(&file, r#"TEMPLATE = """Data, # not a docstring."""
+
+class Empty:
+    pass
+
+def f(x):
+    return x
+
+def g(): pass"#)?;
+    Ok(())
+}
+
+#[test]
+fn test_clean_keeps_blank_lines_and_indentation() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let source = "fn main() {\n    let a = 1; // one\n\n    // gone\n\n\n\n    if a == 1 {\n        /* two */ run(a);\n    }\n}\n";
+    fs::write(root.join("main.rs"), source)?;
+    fs::write(root.join("plain.rs"), "fn f() {}   \n\n\n\nfn g() {}\n")?;
+
+    let clean = |collapse_blank_lines: Option<usize>| {
+        handle_command(&Commands::Clean(CleanArgs {
+            args: Args { directory: root.to_path_buf(), ..Default::default() },
+            collapse_blank_lines,
+            ..Default::default()
+        }))
+    };
+    clean(None)?;
+    // Only the line that held nothing but a comment goes; the blank lines around it stay.
+    assert_eq!(
+        fs::read_to_string(root.join("main.rs"))?,
+        "fn main() {\n    let a = 1;\n\n\n\n\n    if a == 1 {\n        run(a);\n    }\n}\n"
+    );
+    assert_eq!(fs::read_to_string(root.join("plain.rs"))?, "fn f() {}   \n\n\n\nfn g() {}\n");
+
+    clean(Some(1))?;
+    assert_eq!(fs::read_to_string(root.join("main.rs"))?, "fn main() {\n    let a = 1;\n\n    if a == 1 {\n        run(a);\n    }\n}\n");
+    assert_eq!(fs::read_to_string(root.join("plain.rs"))?, "fn f() {}   \n\nfn g() {}\n");
+    Ok(())
+}
+
+#[test]
+fn test_strip_docstrings_from_config_and_keep_docstrings_flag() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(root.join(".filedress.toml"), "[clean]\nstrip_docstrings = true\n")?;
+    fs::write(root.join("a.py"), "def f():\n    \"\"\"Docs.\"\"\"\n    return 1  # one\n")?;
+
+    let run = |extra: &[&str]| std::process::Command::new(env!("CARGO_BIN_EXE_filedress")).arg("clean").arg(root).args(extra).status();
+    assert!(run(&["--keep-docstrings"])?.success());
+    assert_eq!(fs::read_to_string(root.join("a.py"))?, "def f():\n    \"\"\"Docs.\"\"\"\n    return 1\n");
+    assert!(run(&[])?.success());
+    assert_eq!(fs::read_to_string(root.join("a.py"))?, "def f():\n    return 1\n");
+    Ok(())
+}
+
+#[test]
+fn test_keep_docs_from_config_and_per_language() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(root.join(".filedress.toml"), "[clean]\nkeep_docs = true\n\n[languages.java]\nkeep_docs = false\n")?;
+    fs::write(root.join("lib.rs"), "/// Docs\n// note\nfn f() {}\n")?;
+    fs::write(root.join("A.java"), "/** Docs */\n// note\nclass A {}\n")?;
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_filedress")).arg("clean").arg(root).status()?;
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(root.join("lib.rs"))?, "/// Docs\nfn f() {}\n");
+    assert_eq!(fs::read_to_string(root.join("A.java"))?, "class A {}\n");
+    Ok(())
+}
+
+#[test]
+fn test_clean_preserves_directive_comments() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let files = [
+        (
+            "a.ts",
+            "// eslint-disable-next-line no-console\nconsole.log(1); // log it\n// @ts-expect-error\nlet x: number = 'a';\n/* prettier-ignore */\nconst m = [1,0,\n  0,1];\n",
+            "// eslint-disable-next-line no-console\nconsole.log(1);\n// @ts-expect-error\nlet x: number = 'a';\n/* prettier-ignore */\nconst m = [1,0,\n  0,1];\n",
+        ),
+        (
+            "b.py",
+            "import os  # noqa: F401\nx = f()  # type: ignore[attr-defined]\n# just a note\n#region setup\ny = 1\n",
+            "import os  # noqa: F401\nx = f()  # type: ignore[attr-defined]\n#region setup\ny = 1\n",
+        ),
+        ("c.rb", "# frozen_string_literal: true\n# rubocop:disable Style/Foo\n# note\nputs 1\n", "# frozen_string_literal: true\n# rubocop:disable Style/Foo\nputs 1\n"),
+        ("d.rs", "// SAFETY: the pointer is valid\nunsafe { f() } // why\n", "// SAFETY: the pointer is valid\nunsafe { f() }\n"),
+        ("e.swift", "// swiftlint:disable force_cast\n// MARK: - Setup\n// note\nlet a = 1\n", "// swiftlint:disable force_cast\n// MARK: - Setup\nlet a = 1\n"),
+        ("f.hs", "{-# LANGUAGE OverloadedStrings #-}\n{- note -}\nmain = pure ()\n", "{-# LANGUAGE OverloadedStrings #-}\nmain = pure ()\n"),
+        ("g.go", "//go:generate stringer -type=Pill\n// note\npackage main\n", "//go:generate stringer -type=Pill\npackage main\n"),
+        ("h.js", "// TODO(ann): remove\n// note\nf();\n", "// TODO(ann): remove\nf();\n"),
+    ];
+    for (name, input, _) in files {
+        fs::write(root.join(name), input)?;
+    }
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+        .args(["clean", "--keep-pattern", "^TODO\\("])
+        .arg(root)
+        .output()?;
+    assert!(output.status.success());
+    for (name, _, expected) in files {
+        assert_eq!(fs::read_to_string(root.join(name))?, expected, "{}", name);
+    }
+    // The preserved comments are listed under their file.
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains(&format!("  [PRESERVED] {}:1: // eslint-disable-next-line no-console", root.join("a.ts").display())), "{}", stdout);
+    assert!(stdout.contains(&format!("  [PRESERVED] {}:2: # type: ignore[attr-defined]", root.join("b.py").display())), "{}", stdout);
+    assert!(stdout.contains(&format!("  [PRESERVED] {}:1: // TODO(ann): remove", root.join("h.js").display())), "{}", stdout);
+    Ok(())
+}
+
+#[test]
+fn test_keep_patterns_and_directives_from_config() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(
+        root.join(".filedress.toml"),
+        "[clean]\nkeep_patterns = [\"^HACK\"]\n\n[languages.python]\ndirectives = [\"^keep:\"]\n",
+    )?;
+    fs::write(root.join("a.py"), "# HACK: works around a bug\n# keep: this one\n# noqa\nx = 1\n")?;
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_filedress")).arg("clean").arg(root).status()?;
+    assert!(status.success());
+    // A language's `directives` replace its built-in ones.
+    assert_eq!(fs::read_to_string(root.join("a.py"))?, "# HACK: works around a bug\n# keep: this one\nx = 1\n");
+    Ok(())
+}
+
+#[test]
+fn test_user_defined_language_from_project_config() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("infra");
+    fs::create_dir_all(&root)?;
+    fs::write(
+        root.join(".filedress.toml"),
+        r##"
+[languages.terraform]
+extensions = ["tf"]
+line_comment = "#"
+block_comment = ["/*", "*/"]
+strings = ["\""]
+"##,
+    )?;
+    let tf_file = root.join("main.tf");
+    fs::write(&tf_file, "resource \"null\" \"x\" {} # trailing note\n/* block */\n")?;
+
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["tf".to_string()]),
+        ..Default::default()
+    };
+    handle_command(&Commands::Add(args))?;
+    assert!(fs::read_to_string(&tf_file)?.starts_with("# Path:main.tf\n"));
+
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["tf".to_string()]),
+        ..Default::default()
+    };
+    handle_command(&Commands::Clean(CleanArgs { args, ..Default::default() }))?;
+    assert_file_content(&tf_file, "# Path:main.tf\nresource \"null\" \"x\" {}")?;
+
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["tf".to_string()]),
+        ..Default::default()
+    };
+    handle_command(&Commands::Remove(args))?;
+    assert_file_content(&tf_file, "resource \"null\" \"x\" {}")?;
+    Ok(())
+}
+
+#[test]
+fn test_walker_respects_ignore_files() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("web");
+    for dir in ["src", "node_modules/lib", "vendor", "dist"] {
+        fs::create_dir_all(root.join(dir))?;
+    }
+    fs::write(root.join(".gitignore"), "node_modules/\n")?;
+    fs::write(root.join(".ignore"), "dist/\n")?;
+    fs::write(root.join(".filedressignore"), "vendor/\n")?;
+    let files = ["src/app.js", "node_modules/lib/index.js", "vendor/jquery.js", "dist/bundle.js"];
+    for file in files {
+        fs::write(root.join(file), "let x = 1;\n")?;
+    }
+
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["js".to_string()]),
+        ..Default::default()
+    };
+    handle_command(&Commands::Add(args))?;
+
+    assert!(fs::read_to_string(root.join("src/app.js"))?.starts_with("// Path:"));
+    for ignored in &files[1..] {
+        assert_eq!(fs::read_to_string(root.join(ignored))?, "let x = 1;\n", "{} should be ignored", ignored);
+    }
+
+    // --no-ignore processes everything.
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["js".to_string()]),
+        no_ignore: true,
+        ..Default::default()
+    };
+    handle_command(&Commands::Add(args))?;
+    for file in files {
+        assert!(fs::read_to_string(root.join(file))?.starts_with("// Path:"), "{} should have a header", file);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_include_and_exclude_globs() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("app");
+    fs::create_dir_all(root.join("src/generated"))?;
+    fs::create_dir_all(root.join("scripts"))?;
+    let files = ["src/main.ts", "src/main.test.ts", "src/generated/api.ts", "scripts/build.ts"];
+    for file in files {
+        fs::write(root.join(file), "export {};\n")?;
+    }
+
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["ts".to_string()]),
+        include: vec!["src/**".to_string()],
+        exclude: vec!["*.test.ts".to_string(), "src/generated/**".to_string()],
+        ..Default::default()
+    };
+    handle_command(&Commands::Add(args))?;
+
+    assert!(fs::read_to_string(root.join("src/main.ts"))?.starts_with("// Path:src/main.ts"));
+    for skipped in &files[1..] {
+        assert_eq!(fs::read_to_string(root.join(skipped))?, "export {};\n", "{} should be skipped", skipped);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_files_matched_by_name() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("repo");
+    fs::create_dir_all(root.join("docker"))?;
+    let files = ["Dockerfile", "docker/Dockerfile.prod", "Makefile", "Jenkinsfile", ".bashrc", ".env.local", "CMakeLists.txt"];
+    for file in files {
+        fs::write(root.join(file), "value\n")?;
+    }
+    fs::write(root.join("notes.txt"), "value\n")?;
+
+    let args = Args {
+        directory: root.clone(),
+        ..Default::default()
+    };
+    handle_command(&Commands::Add(args))?;
+
+    let first_line = |file: &str| -> Result<String> {
+        Ok(fs::read_to_string(root.join(file))?.lines().next().unwrap_or("").to_string())
+    };
+    assert_eq!(first_line("Dockerfile")?, "# Path:Dockerfile");
+    assert_eq!(first_line("docker/Dockerfile.prod")?, format!("# Path:{}", Path::new("docker").join("Dockerfile.prod").display()));
+    assert_eq!(first_line("Makefile")?, "# Path:Makefile");
+    assert_eq!(first_line("Jenkinsfile")?, "// Path:Jenkinsfile");
+    assert_eq!(first_line(".bashrc")?, "# Path:.bashrc");
+    assert_eq!(first_line(".env.local")?, "# Path:.env.local");
+    assert_eq!(first_line("CMakeLists.txt")?, "# Path:CMakeLists.txt");
+    assert_eq!(first_line("notes.txt")?, "value");
+
+    // Exact file names can be targeted with --exts.
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["Makefile".to_string()]),
+        ..Default::default()
+    };
+    handle_command(&Commands::Remove(args))?;
+    assert_eq!(first_line("Makefile")?, "value");
+    assert_eq!(first_line("Dockerfile")?, "# Path:Dockerfile");
+    Ok(())
+}
+
+#[test]
+fn test_extensionless_scripts_detected_by_shebang() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("tools");
+    fs::create_dir_all(root.join("bin"))?;
+    let deploy = root.join("bin").join("deploy");
+    let run = root.join("bin").join("run");
+    let deploy_content = "#!/usr/bin/env python3\nprint('deploy')  # say it\n";
+    fs::write(&deploy, deploy_content)?;
+    fs::write(&run, "#!/bin/bash\necho run\n")?;
+    fs::write(root.join("bin").join("data"), "no shebang here\n")?;
+
+    let args = Args {
+        directory: root.clone(),
+        project: Some(filedress::cli::ProjectType::Python),
+        ..Default::default()
+    };
+    handle_command(&Commands::Add(args))?;
+    let expected_header = format!("# Path:{}", Path::new("bin").join("deploy").display());
+    assert_eq!(
+        fs::read_to_string(&deploy)?,
+        format!("#!/usr/bin/env python3\n{}\nprint('deploy')  # say it\n", expected_header)
+    );
+    assert_eq!(fs::read_to_string(&run)?, "#!/bin/bash\necho run\n");
+
+    let args = Args {
+        directory: root.clone(),
+        ..Default::default()
+    };
+    handle_command(&Commands::Clean(CleanArgs { args, ..Default::default() }))?;
+    assert_file_content(&deploy, &format!("#!/usr/bin/env python3\n{}\nprint('deploy')", expected_header))?;
+    assert_eq!(fs::read_to_string(root.join("bin").join("data"))?, "no shebang here\n");
+
+    let args = Args {
+        directory: root.clone(),
+        exts: Some(vec!["py".to_string()]),
+        ..Default::default()
+    };
+    handle_command(&Commands::Remove(args))?;
+    assert_eq!(fs::read_to_string(&deploy)?, "#!/usr/bin/env python3\nprint('deploy')\n");
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_leaves_files_untouched() -> Result<()> {
+    let env = setup_clean_test_files()?;
+    let before: Vec<String> = [&env.python_file, &env.rust_file, &env.css_file]
+        .iter()
+        .map(fs::read_to_string)
+        .collect::<Result<_, _>>()?;
+
+    let clean = |args| Commands::Clean(CleanArgs { args, ..Default::default() });
+    let commands: [fn(Args) -> Commands; 3] = [Commands::Add, clean, Commands::Remove];
+    for command in commands {
+        let args = Args {
+            directory: env.root.clone(),
+            force: true,
+            dry_run: true,
+            ..Default::default()
+        };
+        handle_command(&command(args))?;
+    }
+
+    let after: Vec<String> = [&env.python_file, &env.rust_file, &env.css_file]
+        .iter()
+        .map(fs::read_to_string)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(before, after);
+    Ok(())
+}
+
+#[test]
+fn test_structure_dry_run_creates_nothing() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let template = temp_dir.path().join("template.txt");
+    fs::write(&template, "app/\n    src/\n        main.rs\n    Cargo.toml\n")?;
+    let out = temp_dir.path().join("out");
+
+    handle_command(&Commands::Structure(filedress::cli::StructureArgs {
+        file: Some(template),
+        directory: Some(out.clone()),
+        indent: 4,
+        dry_run: true,
+    }))?;
+    assert!(!out.exists());
+    Ok(())
+}
+
+#[test]
+fn test_structure_reads_config_from_target_directory() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let template = temp_dir.path().join("layout.txt");
+    fs::write(&template, "app/\n  main.rs\n")?;
+    let out = temp_dir.path().join("out");
+    fs::create_dir_all(&out)?;
+    fs::write(out.join(".filedress.toml"), "[structure]\nindent = 2\n")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+        .args(["structure".as_ref(), "-f".as_ref(), template.as_os_str(), "-d".as_ref(), out.as_os_str()])
+        .env("FILEDRESS_STATE_DIR", temp_dir.path().join("state"))
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .env("HOME", temp_dir.path())
+        .current_dir(temp_dir.path())
+        .output()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(out.join("app/main.rs").is_file());
+    Ok(())
+}
+
+#[test]
+fn test_check_reports_missing_stale_and_malformed_headers() -> Result<()> {
+    let env = setup_test_environment()?;
+    let check_args = || filedress::cli::CheckArgs {
+        args: Args { directory: env.project_root.clone(), ..Default::default() },
+        format: filedress::cli::OutputFormat::Text,
+    };
+
+    // Nothing has a header yet.
+    let err = handle_command(&Commands::Check(check_args())).unwrap_err();
+    assert!(err.to_string().starts_with("2 of 2"), "{}", err);
+
+    handle_command(&Commands::Add(Args { directory: env.project_root.clone(), ..Default::default() }))?;
+    handle_command(&Commands::Check(check_args()))?;
+
+    // A moved file keeps its old header, and a header in the wrong comment style is malformed.
+    let moved = env.project_root.join("settings.py");
+    fs::rename(&env.config_file, &moved)?;
+    fs::write(&env.user_file, "// Path:src/api/v1/user.py\npass")?;
+    let err = handle_command(&Commands::Check(check_args())).unwrap_err();
+    assert!(err.to_string().starts_with("2 of 2"), "{}", err);
+
+    // Whitespace variations are not violations.
+    fs::write(&moved, "#  Path: settings.py  \npass")?;
+    fs::write(&env.user_file, "#Path:src/api/v1/user.py\npass")?;
+    handle_command(&Commands::Check(check_args()))?;
+    Ok(())
+}
+
+#[test]
+fn test_check_json_output_and_exit_code() -> Result<()> {
+    let temp_dir = tempdir()?;
+    fs::write(temp_dir.path().join("a.py"), "# Path:b.py\npass\n")?;
+    fs::write(temp_dir.path().join("c.rs"), "fn main() {}\n")?;
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+        .args(["check", "--format", "json"])
+        .arg(temp_dir.path())
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .env("HOME", temp_dir.path())
+        .output()?;
+    assert!(!output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(report["files_checked"], 2);
+    let violations = report["violations"].as_array().context("violations array")?;
+    assert_eq!(violations[0]["file"], "a.py");
+    assert_eq!(violations[0]["problem"], "stale");
+    assert_eq!(violations[0]["expected"], "# Path:a.py");
+    assert_eq!(violations[0]["found"], "# Path:b.py");
+    assert_eq!(violations[1]["problem"], "missing");
+    assert!(violations[1]["found"].is_null());
+    Ok(())
+}
+
+#[test]
+fn test_parallel_runs_match_serial_runs() -> Result<()> {
+    let temp_dir = tempdir()?;
+    for dir in ["a", "b/c", "d"] {
+        let dir = temp_dir.path().join("project").join(dir);
+        fs::create_dir_all(&dir)?;
+        for i in 0..20 {
+            fs::write(dir.join(format!("m{}.py", i)), format!("x = {}  # value\n", i))?;
+        }
+    }
+    let serial = temp_dir.path().join("serial");
+    let parallel = temp_dir.path().join("parallel");
+    fs::create_dir_all(&serial)?;
+    fs::create_dir_all(&parallel)?;
+    copy_dir(&temp_dir.path().join("project"), &serial)?;
+    copy_dir(&temp_dir.path().join("project"), &parallel)?;
+
+    for (root, jobs) in [(&serial, 1), (&parallel, 8)] {
+        let args = || Args { directory: root.clone(), jobs: Some(jobs), ..Default::default() };
+        handle_command(&Commands::Add(args()))?;
+        handle_command(&Commands::Clean(CleanArgs { args: args(), ..Default::default() }))?;
+        handle_command(&Commands::Copy(Args { output: Some(root.with_extension("txt")), ..args() }))?;
+    }
+
+    let serial_bundle = fs::read_to_string(serial.with_extension("txt"))?;
+    assert_eq!(serial_bundle, fs::read_to_string(parallel.with_extension("txt"))?);
+    assert!(serial_bundle.starts_with("FILE: a/m0.py\n---\n\n# Path:a/m0.py\nx = 0\n"));
+    assert!(serial_bundle.find("FILE: a/m9.py").unwrap() < serial_bundle.find("FILE: b/c/m0.py").unwrap());
+    Ok(())
+}
+
+/// Recursively copies the files under `from` into `to`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_header_goes_after_preamble() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    let files = [
+        ("enc.py", "# -*- coding: utf-8 -*-\nimport os\n", "# -*- coding: utf-8 -*-\n# Path:enc.py\nimport os\n"),
+        ("feed.xml", "<?xml version=\"1.0\"?>\n<feed/>\n", "<?xml version=\"1.0\"?>\n<!-- Path: feed.xml -->\n<feed/>\n"),
+        ("index.php", "<?php\necho 1;\n", "<?php\n// Path:index.php\necho 1;\n"),
+        // A blank line keeps Go from treating the header as the package doc comment.
+        ("main.go", "package main\n", "// Path:main.go\n\npackage main\n"),
+        ("os.go", "//go:build linux\n\npackage os\n", "//go:build linux\n\n// Path:os.go\n\npackage os\n"),
+        ("page.tsx", "\"use client\";\nexport {};\n", "\"use client\";\n// Path:page.tsx\nexport {};\n"),
+        ("post.md", "---\ntitle: Hi\n---\n# Hi\n", "---\ntitle: Hi\n---\n<!-- Path: post.md -->\n# Hi\n"),
+    ];
+    for (name, content, _) in &files {
+        fs::write(root.join(name), content)?;
+    }
+    let args = || Args { directory: root.to_path_buf(), ..Default::default() };
+
+    handle_command(&Commands::Add(args()))?;
+    for (name, _, expected) in &files {
+        assert_eq!(&fs::read_to_string(root.join(name))?, expected, "{}", name);
+    }
+
+    // --force finds and replaces the header at the same position instead of adding a second one.
+    handle_command(&Commands::Add(Args { force: true, ..args() }))?;
+    for (name, _, expected) in &files {
+        assert_eq!(&fs::read_to_string(root.join(name))?, expected, "{}", name);
+    }
+
+    handle_command(&Commands::Remove(args()))?;
+    for (name, original, _) in &files {
+        assert_eq!(&fs::read_to_string(root.join(name))?, original, "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_custom_header_template_and_migrate() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(
+        root.join(".filedress.toml"),
+        "[header]\ntemplate = \"File: {path}\\nModule: {module}\"\nlegacy_templates = [\"FILE: {path}\"]\n",
+    )?;
+    fs::create_dir_all(root.join("pkg"))?;
+    fs::write(root.join("pkg/old.py"), "# Path:pkg/old.py\nx = 1\n")?;
+    fs::write(root.join("pkg/older.py"), "# FILE: pkg/older.py\nx = 2\n")?;
+    fs::write(root.join("pkg/new.py"), "x = 3\n")?;
+    let args = || Args { directory: root.to_path_buf(), ..Default::default() };
+
+    // Legacy headers are recognised, so they are not duplicated.
+    handle_command(&Commands::Add(args()))?;
+    assert_file_content(&root.join("pkg/new.py"), "# File: pkg/new.py\n# Module: pkg.new\nx = 3\n")?;
+    assert_file_content(&root.join("pkg/old.py"), "# Path:pkg/old.py\nx = 1\n")?;
+
+    handle_command(&Commands::Add(Args { migrate: true, ..args() }))?;
+    assert_file_content(&root.join("pkg/old.py"), "# File: pkg/old.py\n# Module: pkg.old\nx = 1\n")?;
+    assert_file_content(&root.join("pkg/older.py"), "# File: pkg/older.py\n# Module: pkg.older\nx = 2\n")?;
+
+    let check = filedress::cli::CheckArgs { args: args(), format: filedress::cli::OutputFormat::Text };
+    handle_command(&Commands::Check(check))?;
+
+    handle_command(&Commands::Remove(args()))?;
+    assert_file_content(&root.join("pkg/old.py"), "x = 1\n")?;
+    assert_file_content(&root.join("pkg/new.py"), "x = 3\n")?;
+    Ok(())
+}
+
+#[test]
+fn test_license_command_adds_updates_and_checks() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(root.join(".filedress.toml"), "[license]\nspdx = \"MIT\"\nholder = \"Acme\"\n")?;
+    fs::write(root.join("new.py"), "# Path:new.py\nimport os\n")?;
+    fs::write(root.join("old.c"), "/*\n * Copyright (c) 2019 Acme\n * SPDX-License-Identifier: GPL-2.0\n */\nint x;\n")?;
+    fs::write(root.join("bare.c"), "/*\n   Copyright 2020 Acme\n   SPDX-License-Identifier: MIT\n*/\nint y;\n")?;
+    let year = 2031;
+    let license = |check: bool| filedress::cli::LicenseArgs {
+        args: Args { directory: root.to_path_buf(), ..Default::default() },
+        spdx: None,
+        holder: None,
+        check,
+    };
+    // SAFETY: no other test reads SOURCE_DATE_EPOCH concurrently with a different expectation.
+    unsafe { std::env::set_var("SOURCE_DATE_EPOCH", "1924992000") }; // 2031-01-01
+
+    assert!(handle_command(&Commands::License(license(true))).is_err());
+    handle_command(&Commands::License(license(false)))?;
+    assert_file_content(
+        &root.join("new.py"),
+        &format!("# Path:new.py\n# SPDX-License-Identifier: MIT\n# Copyright (c) {} Acme\nimport os\n", year),
+    )?;
+    assert_file_content(
+        &root.join("old.c"),
+        &format!("/*\n * Copyright (c) 2019-{} Acme\n * SPDX-License-Identifier: MIT\n */\nint x;\n", year),
+    )?;
+    // A block comment without ` * ` prefixes is updated too, not given a second license.
+    assert_file_content(&root.join("bare.c"), &format!("/*\n   Copyright 2020-{} Acme\n   SPDX-License-Identifier: MIT\n*/\nint y;\n", year))?;
+    handle_command(&Commands::License(license(true)))?;
+
+    // The path header and the license block stay out of each other's way.
+    handle_command(&Commands::Add(Args { directory: root.to_path_buf(), ..Default::default() }))?;
+    handle_command(&Commands::Clean(CleanArgs { args: Args { directory: root.to_path_buf(), ..Default::default() }, ..Default::default() }))?;
+    assert_file_content(
+        &root.join("old.c"),
+        &format!("// Path:old.c\n/*\n * Copyright (c) 2019-{} Acme\n * SPDX-License-Identifier: MIT\n */\nint x;\n", year),
+    )?;
+    assert_file_content(
+        &root.join("bare.c"),
+        &format!("// Path:bare.c\n/*\n   Copyright 2020-{} Acme\n   SPDX-License-Identifier: MIT\n*/\nint y;\n", year),
+    )?;
+    handle_command(&Commands::License(license(true)))?;
+    Ok(())
+}
+
+#[test]
+fn test_sync_rewrites_only_stale_headers() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/new"))?;
+    fs::write(root.join("src/keep.rs"), "// Path:src/keep.rs\nfn keep() {}\n")?;
+    fs::write(root.join("src/new/moved.rs"), "// Path:src/old/moved.rs\n//! Docs.\nfn moved() {}\n")?;
+    fs::write(root.join("src/bare.rs"), "fn bare() {}\n")?;
+    fs::write(root.join("src/lib.rs"), "// Path:src/lib.rs\npub mod keep;\npub mod moved;\n")?;
+    let sync = |git_renames: Option<&str>| {
+        handle_command(&Commands::Sync(filedress::cli::SyncArgs {
+            args: Args { directory: root.to_path_buf(), ..Default::default() },
+            git_renames: git_renames.map(String::from),
+        }))
+    };
+
+    sync(None)?;
+    assert_file_content(&root.join("src/new/moved.rs"), "// Path:src/new/moved.rs\n//! Docs.\nfn moved() {}\n")?;
+    assert_file_content(&root.join("src/keep.rs"), "// Path:src/keep.rs\nfn keep() {}\n")?;
+    // Files without a header are left to `add`.
+    assert_file_content(&root.join("src/bare.rs"), "fn bare() {}\n")?;
+
+    // With --git-renames, only files git saw being moved are touched.
+    let git = |args: &[&str]| -> Result<()> {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()?
+            .status;
+        anyhow::ensure!(status.success(), "git {:?} failed", args);
+        Ok(())
+    };
+    fs::write(root.join("src/keep.rs"), "// Path:stale/keep.rs\nfn keep() {}\n")?;
+    git(&["init", "-q"])?;
+    git(&["add", "."])?;
+    git(&["commit", "-q", "-m", "init"])?;
+    git(&["mv", "src/lib.rs", "src/new/lib.rs"])?;
+
+    sync(Some("HEAD"))?;
+    assert_file_content(&root.join("src/new/lib.rs"), "// Path:src/new/lib.rs\npub mod keep;\npub mod moved;\n")?;
+    assert_file_content(&root.join("src/keep.rs"), "// Path:stale/keep.rs\nfn keep() {}\n")?;
+    assert!(sync(Some("no-such-rev")).is_err());
+    Ok(())
+}
+
+/// Reads a fixture from `tests/test_files` byte for byte.
+fn read_fixture(name: &str) -> Result<Vec<u8>> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("test_files").join(name);
+    fs::read(&path).with_context(|| format!("Failed to read fixture: {}", path.display()))
+}
+
+#[test]
+fn test_line_endings_bom_and_final_newline_are_preserved() -> Result<()> {
+    for name in ["crlf_rust.rs", "bom_python.py"] {
+        let temp_dir = tempdir()?;
+        let root = temp_dir.path();
+        let file = root.join(name);
+        let input = read_fixture(&format!("{}.input", name))?;
+        let args = || Args { directory: root.to_path_buf(), ..Default::default() };
+
+        fs::write(&file, &input)?;
+        handle_command(&Commands::Add(args()))?;
+        assert_eq!(fs::read(&file)?, read_fixture(&format!("{}.add.expected", name))?, "add: {}", name);
+        handle_command(&Commands::Check(filedress::cli::CheckArgs { args: args(), format: Default::default() }))?;
+        handle_command(&Commands::Remove(args()))?;
+        assert_eq!(fs::read(&file)?, input, "remove: {}", name);
+
+        handle_command(&Commands::Clean(CleanArgs { args: args(), ..Default::default() }))?;
+        assert_eq!(fs::read(&file)?, read_fixture(&format!("{}.clean.expected", name))?, "clean: {}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_clean_skips_non_utf8_files_and_copy_keeps_raw_content() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path();
+    fs::write(root.join("b.rs"), "\u{feff}fn b() {} // note\r\n")?;
+    let args = || Args { directory: root.to_path_buf(), ..Default::default() };
+
+    let bundle = root.join("bundle.txt");
+    handle_command(&Commands::Copy(Args { output: Some(bundle.clone()), exts: Some(vec!["rs".to_string()]), ..args() }))?;
+    assert!(fs::read_to_string(&bundle)?.contains("\u{feff}fn b() {} // note\r\n"));
+
+    fs::write(root.join("a.rs"), b"fn a() {} // \xff\xfe\n")?;
+    handle_command(&Commands::Clean(CleanArgs { args: args(), ..Default::default() }))?;
+    assert_eq!(fs::read(root.join("a.rs"))?, b"fn a() {} // \xff\xfe\n");
+    assert_eq!(fs::read_to_string(root.join("b.rs"))?, "\u{feff}fn b() {}\r\n");
+    Ok(())
+}
+
+#[test]
+fn test_add_then_remove_is_the_identity() -> Result<()> {
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("test_files");
+    let mut originals: Vec<(String, String)> = Vec::new();
+    for entry in fs::read_dir(&fixtures)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let Some(name) = name.strip_suffix(".input") else { continue };
+        let content = fs::read_to_string(fixtures.join(format!("{}.input", name)))?;
+        // Fixtures that already have a header are left alone by `add`.
+        if !content.contains("Path:") {
+            originals.push((name.to_string(), content));
+        }
+    }
+    for (name, content) in [
+        ("empty.rs", ""),
+        ("no_newline.py", "x = 1"),
+        ("leading_blank.rs", "\nfn main() {}\n"),
+        ("script.sh", "#!/bin/sh"),
+        ("mixed.js", "'use strict';\r\nlet a = 1;\nlet b = 2;\r\n"),
+        ("page.html", "<?xml version=\"1.0\"?>\r\n<html></html>\r\n"),
+    ] {
+        originals.push((name.to_string(), content.to_string()));
+    }
+
+    for header_config in ["", "[header]\nblank_line = true\n", "[header]\ntemplate = \"File: {path}\\nModule: {module}\"\n"] {
+        let temp_dir = tempdir()?;
+        let root = temp_dir.path();
+        fs::write(root.join(".filedress.toml"), header_config)?;
+        for (name, content) in &originals {
+            fs::write(root.join(name), content)?;
+        }
+        let args = || Args { directory: root.to_path_buf(), ..Default::default() };
+
+        handle_command(&Commands::Add(args()))?;
+        for (name, content) in &originals {
+            assert_ne!(&fs::read_to_string(root.join(name))?, content, "add left {} unchanged with {:?}", name, header_config);
+        }
+        handle_command(&Commands::Remove(args()))?;
+        for (name, content) in &originals {
+            assert_eq!(&fs::read_to_string(root.join(name))?, content, "add + remove changed {} with {:?}", name, header_config);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_undo_restores_runs_and_protects_later_edits() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let project = temp_dir.path().join("project");
+    let state = temp_dir.path().join("state");
+    fs::create_dir_all(&project)?;
+    fs::write(project.join("a.py"), "x = 1  # note\n")?;
+    fs::write(project.join("b.rs"), "fn b() {} // note\n")?;
+    let run = |args: &[&str]| -> Result<std::process::Output> {
+        Ok(std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+            .args(args)
+            .env("FILEDRESS_STATE_DIR", &state)
+            .env("XDG_CONFIG_HOME", temp_dir.path())
+            .env("HOME", temp_dir.path())
+            .current_dir(&project)
+            .output()?)
+    };
+    let project_arg = project.to_str().context("utf-8 path")?;
+
+    assert!(run(&["add", project_arg])?.status.success());
+    assert!(run(&["clean", project_arg])?.status.success());
+    // A broken journal is skipped instead of hiding every other run.
+    fs::write(state.join("journal").join("broken.jsonl"), "{not json")?;
+    let history = String::from_utf8(run(&["history"])?.stdout)?;
+    assert_eq!(history.lines().count(), 3, "{}", history);
+    assert!(history.lines().nth(1).is_some_and(|line| line.contains("clean")), "{}", history);
+
+    // Runs made in another directory are neither listed nor undone from here.
+    let elsewhere = std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+        .arg("undo")
+        .env("FILEDRESS_STATE_DIR", &state)
+        .env("HOME", temp_dir.path())
+        .current_dir(&state)
+        .output()?;
+    assert!(!elsewhere.status.success());
+    assert_eq!(fs::read_to_string(project.join("b.rs"))?, "// Path:b.rs\nfn b() {}\n");
+
+    // The latest run goes first.
+    assert!(run(&["undo"])?.status.success());
+    assert_eq!(fs::read_to_string(project.join("a.py"))?, "# Path:a.py\nx = 1  # note\n");
+
+    // A file edited since the run is only restored with --force.
+    fs::write(project.join("a.py"), "# Path:a.py\nx = 2  # note\n")?;
+    let refused = run(&["undo"])?;
+    assert!(!refused.status.success());
+    assert!(String::from_utf8(refused.stdout)?.contains("[CHANGED]"));
+    assert_eq!(fs::read_to_string(project.join("b.rs"))?, "// Path:b.rs\nfn b() {} // note\n");
+
+    assert!(run(&["undo", "--force"])?.status.success());
+    assert_eq!(fs::read_to_string(project.join("a.py"))?, "x = 1  # note\n");
+    assert_eq!(fs::read_to_string(project.join("b.rs"))?, "fn b() {} // note\n");
+    assert!(!run(&["undo"])?.status.success());
+    Ok(())
+}
+
+#[test]
+fn test_undo_removes_created_structure() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let state = temp_dir.path().join("state");
+    let template = temp_dir.path().join("layout.txt");
+    fs::write(&template, "app/\n    main.rs\n")?;
+    let out = temp_dir.path().join("out");
+    fs::create_dir_all(&out)?;
+    fs::write(out.join("keep.txt"), "mine\n")?;
+    let run = |args: &[&std::ffi::OsStr]| -> Result<bool> {
+        Ok(std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+            .args(args)
+            .env("FILEDRESS_STATE_DIR", &state)
+            .env("XDG_CONFIG_HOME", temp_dir.path())
+            .env("HOME", temp_dir.path())
+            .current_dir(temp_dir.path())
+            .output()?
+            .status
+            .success())
+    };
+
+    assert!(run(&["structure".as_ref(), "-f".as_ref(), template.as_os_str(), "-d".as_ref(), out.as_os_str()])?);
+    assert!(out.join("app/main.rs").exists());
+    assert!(run(&["undo".as_ref()])?);
+    assert!(!out.join("app").exists());
+    assert_eq!(fs::read_to_string(out.join("keep.txt"))?, "mine\n");
+    Ok(())
+}
+
+#[test]
+fn test_comments_exports_json_and_filtered_csv() -> Result<()> {
+    let temp_dir = tempdir()?;
+    let root = temp_dir.path().join("src");
+    fs::create_dir_all(&root)?;
+    fs::write(root.join("lib.rs"), "/// Adds one.\nfn inc(x: i32) -> i32 {\n    /* TODO: overflow,\n       \"checked\" */\n    x + 1 // FIXME later\n}\n")?;
+    fs::write(root.join("a.py"), "def f():\n    \"\"\"Docs.\"\"\"\n    return 1  # noqa: E501\n")?;
+    // The header and license are left out, and `clean`'s keep patterns mark directives.
+    fs::write(root.join("c.js"), "// Path:c.js\n// SPDX-License-Identifier: MIT\n\n// HACK: keep me\nlet x = 1; // plain\n")?;
+    fs::write(root.join(".filedress.toml"), "[clean]\nkeep_patterns = [\"^HACK\"]\n")?;
+    let run = |extra: &[&str]| -> Result<std::process::Output> {
+        Ok(std::process::Command::new(env!("CARGO_BIN_EXE_filedress"))
+            .arg("comments")
+            .arg(&root)
+            .args(extra)
+            .env("XDG_CONFIG_HOME", temp_dir.path())
+            .env("HOME", temp_dir.path())
+            .output()?)
+    };
+
+    let output = run(&["-j", "1"])?;
+    assert!(output.status.success());
+    let comments: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let summary: Vec<String> = comments
+        .as_array()
+        .context("comments array")?
+        .iter()
+        .map(|c| format!("{} {}-{} {} {}", c["file"].as_str().unwrap(), c["start_line"], c["end_line"], c["kind"].as_str().unwrap(), c["text"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            "a.py 2-2 doc Docs.",
+            "a.py 3-3 directive noqa: E501",
+            "c.js 4-4 directive HACK: keep me",
+            "c.js 5-5 line plain",
+            "lib.rs 1-1 doc Adds one.",
+            "lib.rs 3-4 block TODO: overflow,\n       \"checked\"",
+            "lib.rs 5-5 line FIXME later",
+        ]
+    );
+
+    let output = run(&["--format", "csv", "--filter", "TODO|FIXME|HACK"])?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "file,start_line,end_line,kind,text\nc.js,4,4,directive,HACK: keep me\nlib.rs,3,4,block,\"TODO: overflow,\n       \"\"checked\"\"\"\nlib.rs,5,5,line,FIXME later\n"
+    );
+    Ok(())
+}